@@ -86,12 +86,19 @@ interface RipgrepNativeMapping extends Library
 		MISSING_FILENAME = 1,
 		MISSING_SEARCH_TEXT = 2,
 		MISSING_CALLBACK = 3,
+		INVALID_OPTIONS = 4,
 		// Failure from inside ripgrep:
 		ERROR_BAD_PATTERN = 11,
 		ERROR_COULD_NOT_OPEN_FILE = 12,
 		ERROR_FROM_RIPGREP = 13,
+		ERROR_ENGINE_UNAVAILABLE = 14,
+		ERROR_PATTERN_TOO_BIG = 15,
 		// Failure from inside the callback:
-		ERROR_FROM_CALLBACK = 21;
+		ERROR_FROM_CALLBACK = 21,
+		// Not a failure, but the search stopped before reporting every result:
+		SUCCESS_TRUNCATED = 31,
+		// Not a failure, but search_path_exists didn't find a match anywhere:
+		NOT_FOUND = 32;
 
 		// Since this is a utility class, it should not be instantiated.
 		private ErrorCodes() {
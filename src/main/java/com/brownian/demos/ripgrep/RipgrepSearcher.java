@@ -47,6 +47,7 @@ public class RipgrepSearcher
 		switch (resultStatusCode)
 		{
 			case RipgrepNativeMapping.ErrorCodes.SUCCESS:
+			case RipgrepNativeMapping.ErrorCodes.SUCCESS_TRUNCATED:
 				return;
 			case RipgrepNativeMapping.ErrorCodes.MISSING_FILENAME:
 				throw new IllegalStateException("Filename passed to native code was missing or could not be read; this should not happen");
@@ -0,0 +1,157 @@
+// Converts byte offsets within a line into columns, in whichever unit the caller counts in.
+//
+// Java strings index in UTF-16 code units, terminals count grapheme clusters,
+// and ripgrep only knows about bytes, so the conversion happens here instead of on every caller.
+// Lines that aren't valid UTF-8 are decoded lossily, so each invalid sequence counts as one character.
+//
+// Lines too long to report whole are cut down to a preview window here too.
+use std::ops::Range;
+
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::types::ColumnUnit;
+
+// The 0-based column at which the byte `offset` of `line` starts.
+// A tab advances to the next multiple of `tab_width`, unless `tab_width` is 0.
+pub fn column_at(line: &[u8], offset: usize, unit: ColumnUnit, tab_width: u32) -> usize {
+    let prefix = &line[..offset.min(line.len())];
+    let tab_width = tab_width as usize;
+    match unit {
+        ColumnUnit::Byte => count_columns(prefix.iter().map(|&b| (b == b'\t', 1)), tab_width),
+        ColumnUnit::Char => count_columns(
+            String::from_utf8_lossy(prefix)
+                .chars()
+                .map(|c| (c == '\t', 1)),
+            tab_width,
+        ),
+        ColumnUnit::Utf16 => count_columns(
+            String::from_utf8_lossy(prefix)
+                .chars()
+                .map(|c| (c == '\t', c.len_utf16())),
+            tab_width,
+        ),
+        ColumnUnit::Grapheme => count_columns(
+            String::from_utf8_lossy(prefix)
+                .graphemes(true)
+                .map(|g| (g == "\t", 1)),
+            tab_width,
+        ),
+    }
+}
+
+// The part of `range` to report when it's longer than `width` bytes: a window of at most `width` bytes,
+// centered on `focus` if there is one, and otherwise at the start of the range.
+// The window never cuts a UTF-8 character in half, so it can come out a little narrower than `width`.
+pub fn preview_window(
+    line: &[u8],
+    range: Range<usize>,
+    focus: Option<Range<usize>>,
+    width: usize,
+) -> Range<usize> {
+    if range.len() <= width {
+        return range;
+    }
+    let start = match focus {
+        Some(focus) => (focus.start + focus.len() / 2).saturating_sub(width / 2),
+        None => range.start,
+    };
+    let mut start = start.clamp(range.start, range.end - width);
+    let mut end = start + width;
+    while start < end && is_continuation_byte(line[start]) {
+        start += 1;
+    }
+    while end > start && end < range.end && is_continuation_byte(line[end]) {
+        end -= 1;
+    }
+    start..end
+}
+
+fn is_continuation_byte(byte: u8) -> bool {
+    byte & 0b1100_0000 == 0b1000_0000
+}
+
+// Adds up the widths of the pieces of a line, given as (is it a tab, width) pairs
+fn count_columns(pieces: impl Iterator<Item = (bool, usize)>, tab_width: usize) -> usize {
+    pieces.fold(0, |column, (is_tab, width)| {
+        if is_tab && tab_width > 0 {
+            column + tab_width - column % tab_width
+        } else {
+            column + width
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // "é" is 2 bytes, "😀" is 4 bytes and 2 UTF-16 code units,
+    // and "e\u{301}" is an "e" with a combining accent: 3 bytes, 2 chars, but 1 grapheme
+    const LINE: &str = "é😀e\u{301}bee";
+
+    fn offset_of_bee() -> usize {
+        LINE.find("bee").unwrap()
+    }
+
+    #[test]
+    fn test_columns_in_each_unit() {
+        let offset = offset_of_bee();
+        assert_eq!(9, column_at(LINE.as_bytes(), offset, ColumnUnit::Byte, 0));
+        assert_eq!(4, column_at(LINE.as_bytes(), offset, ColumnUnit::Char, 0));
+        assert_eq!(5, column_at(LINE.as_bytes(), offset, ColumnUnit::Utf16, 0));
+        assert_eq!(
+            3,
+            column_at(LINE.as_bytes(), offset, ColumnUnit::Grapheme, 0)
+        );
+    }
+
+    #[test]
+    fn test_tabs_advance_to_the_next_tab_stop() {
+        let line = b"a\tbc\tbee";
+        assert_eq!(5, column_at(line, 5, ColumnUnit::Byte, 0));
+        assert_eq!(8, column_at(line, 5, ColumnUnit::Byte, 4));
+        assert_eq!(8, column_at(line, 5, ColumnUnit::Grapheme, 4));
+        assert_eq!(4, column_at(line, 2, ColumnUnit::Char, 4));
+    }
+
+    #[test]
+    fn test_invalid_utf8_counts_as_one_char() {
+        let line = b"\xffbee";
+        assert_eq!(1, column_at(line, 1, ColumnUnit::Char, 0));
+        assert_eq!(1, column_at(line, 1, ColumnUnit::Byte, 0));
+    }
+
+    #[test]
+    fn test_short_range_is_not_cut() {
+        assert_eq!(0..5, preview_window(b"bees!", 0..5, Some(1..3), 5));
+    }
+
+    #[test]
+    fn test_preview_is_centered_on_focus() {
+        let line = b"0123456789bee0123456789";
+        let window = preview_window(line, 0..line.len(), Some(10..13), 7);
+
+        assert_eq!(8..15, window);
+        assert_eq!(b"89bee01", &line[window]);
+    }
+
+    #[test]
+    fn test_preview_is_clamped_to_range() {
+        let line = b"bee0123456789";
+
+        assert_eq!(0..5, preview_window(line, 0..line.len(), Some(0..3), 5));
+        assert_eq!(8..13, preview_window(line, 0..line.len(), Some(12..13), 5));
+        assert_eq!(0..5, preview_window(line, 0..line.len(), None, 5));
+    }
+
+    #[test]
+    fn test_preview_does_not_cut_characters() {
+        // each "é" is 2 bytes
+        let line = "éééééé".as_bytes();
+        let window = preview_window(line, 0..line.len(), Some(5..7), 5);
+
+        assert!(std::str::from_utf8(&line[window.clone()]).is_ok());
+        assert!(window.len() <= 5);
+        assert!(window.contains(&5));
+    }
+}
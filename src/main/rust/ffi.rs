@@ -1,4 +1,5 @@
-use std::os::raw::c_char;
+use std::os::raw::{c_char, c_int};
+use std::ptr;
 
 pub use crate::types::*;
 
@@ -8,12 +9,140 @@ pub extern "C" fn search_path(
     filename: *const c_char,
     search_text: *const c_char,
     result_callback: Option<SearchResultCallbackFn>,
+) -> SearchStatusCode {
+    search_path_with_options(filename, search_text, ptr::null(), result_callback)
+}
+
+#[no_mangle]
+pub extern "C" fn search_path_with_options(
+    // every Java type is nullable, represented here as an Option<*type>
+    filename: *const c_char,
+    search_text: *const c_char,
+    options: *const SearchOptions,
+    result_callback: Option<SearchResultCallbackFn>,
+) -> SearchStatusCode {
+    use SearchStatusCode::*;
+
+    match core::search_path(filename, search_text, options, result_callback) {
+        Ok(_) => Success,
+        Err(code) => code,
+    }
+}
+
+// Searches for every literal in a (possibly huge) dictionary at once.
+// Each result's `pattern_index` says which dictionary entry matched.
+#[no_mangle]
+pub extern "C" fn search_path_literals(
+    // every Java type is nullable, represented here as an Option<*type>
+    filename: *const c_char,
+    literals: *const *const c_char,
+    num_literals: c_int,
+    options: *const SearchOptions,
+    result_callback: Option<SearchResultCallbackFn>,
+) -> SearchStatusCode {
+    use SearchStatusCode::*;
+
+    match core::search_path_literals(filename, literals, num_literals, options, result_callback) {
+        Ok(_) => Success,
+        Err(code) => code,
+    }
+}
+
+// Searches for a literal approximately, allowing up to `max_distance` insertions, deletions or substitutions.
+// Each result's `edit_distance` says how close the closest match on that line is.
+#[no_mangle]
+pub extern "C" fn search_path_fuzzy(
+    // every Java type is nullable, represented here as an Option<*type>
+    filename: *const c_char,
+    search_text: *const c_char,
+    max_distance: c_int,
+    options: *const SearchOptions,
+    result_callback: Option<SearchResultCallbackFn>,
+) -> SearchStatusCode {
+    use SearchStatusCode::*;
+
+    match core::search_path_fuzzy(
+        filename,
+        search_text,
+        max_distance,
+        options,
+        result_callback,
+    ) {
+        Ok(_) => Success,
+        Err(code) => code,
+    }
+}
+
+// Searches for a pasted snippet of text, where any run of whitespace matches any run of whitespace,
+// including line breaks. Each match is one result, from the line it starts on to the line it ends on.
+#[no_mangle]
+pub extern "C" fn search_path_snippet(
+    // every Java type is nullable, represented here as an Option<*type>
+    filename: *const c_char,
+    snippet: *const c_char,
+    options: *const SearchOptions,
+    result_callback: Option<SearchResultCallbackFn>,
 ) -> SearchStatusCode {
     use SearchStatusCode::*;
 
-    match core::search_path(filename, search_text, result_callback) {
-        Ok(_) => return Success,
-        Err(code) => return code,
+    match core::search_path_snippet(filename, snippet, options, result_callback) {
+        Ok(_) => Success,
+        Err(code) => code,
+    }
+}
+
+// Lists the files with a match, or the files without one, calling back once with each file's path.
+// Each file's search stops at its first match.
+#[no_mangle]
+pub extern "C" fn search_path_files(
+    // every Java type is nullable, represented here as an Option<*type>
+    filename: *const c_char,
+    search_text: *const c_char,
+    options: *const SearchOptions,
+    // a `FileListMode`
+    mode: c_int,
+    path_callback: Option<FilePathCallbackFn>,
+) -> SearchStatusCode {
+    use SearchStatusCode::*;
+
+    match core::search_path_files(filename, search_text, options, mode, path_callback) {
+        Ok(_) => Success,
+        Err(code) => code,
+    }
+}
+
+// Checks whether anything under the path matches, like `rg --quiet`, stopping at the first match.
+// Returns `Success` if something matched, `NotFound` if nothing did, or an error code. There's no callback.
+#[no_mangle]
+pub extern "C" fn search_path_exists(
+    // every Java type is nullable, represented here as an Option<*type>
+    filename: *const c_char,
+    search_text: *const c_char,
+    options: *const SearchOptions,
+) -> SearchStatusCode {
+    use SearchStatusCode::*;
+
+    match core::search_path_exists(filename, search_text, options) {
+        Ok(true) => Success,
+        Ok(false) => NotFound,
+        Err(code) => code,
+    }
+}
+
+// Checks whether a pattern would compile with the given options, without searching anything.
+// When it wouldn't, the diagnostic (if not null) describes what's wrong and where.
+#[no_mangle]
+pub extern "C" fn pattern_validate(
+    // every Java type is nullable, represented here as an Option<*type>
+    search_text: *const c_char,
+    options: *const SearchOptions,
+    diagnostic: *mut PatternDiagnostic,
+) -> SearchStatusCode {
+    use SearchStatusCode::*;
+
+    match core::pattern_validate(search_text, options, diagnostic) {
+        Ok(_) => Success,
+        Err(code) => code,
     }
 }
 
@@ -40,15 +169,20 @@ pub extern "C" fn search_dir(
 }
 
 mod core {
-    use std::os::raw::c_char;
+    use std::convert::TryFrom;
+    use std::ffi::CString;
+    use std::fs;
+    use std::os::raw::{c_char, c_int};
     use std::path::Path;
+    use std::ptr;
     use std::result::Result;
 
-    use grep::regex::RegexMatcher;
-    use grep::searcher::Searcher;
+    use grep::matcher::{LineTerminator, Matcher};
+    use grep::searcher::{Searcher, SearcherBuilder};
     use walkdir::*;
     use walkdir::DirEntry;
 
+    use crate::normalize::NormalizingMatcher;
     use crate::parse::*;
     use crate::types::*;
 
@@ -56,43 +190,459 @@ mod core {
         // every Java type is nullable, represented here as an Option<*type>
         filename: *const c_char,
         search_text: *const c_char,
+        options: *const SearchOptions,
         result_callback: Option<SearchResultCallbackFn>,
     ) -> Result<(), SearchStatusCode> {
         use SearchStatusCode::*;
 
+        let options = parse_options(options)?;
         let path = parse_path(filename)?;
-        let matcher: RegexMatcher = parse_search_text(search_text)?;
+        let matcher = parse_pattern(search_text, &options)?;
         let callback = result_callback.ok_or(MissingCallback)?;
 
+        let search = ResultSearch {
+            path: &path,
+            search_text,
+            options: &options,
+            callback,
+        };
+        run_with_pattern(matcher, &options, search)
+    }
+
+    pub fn search_path_literals(
+        // every Java type is nullable, represented here as an Option<*type>
+        filename: *const c_char,
+        literals: *const *const c_char,
+        num_literals: c_int,
+        options: *const SearchOptions,
+        result_callback: Option<SearchResultCallbackFn>,
+    ) -> Result<(), SearchStatusCode> {
+        use SearchStatusCode::*;
+
+        let options = parse_options(options)?;
+        let path = parse_path(filename)?;
+        let matcher = parse_literals(literals, num_literals, &options)?;
+        let callback = result_callback.ok_or(MissingCallback)?;
+
+        let search = ResultSearch {
+            path: &path,
+            search_text: ptr::null(),
+            options: &options,
+            callback,
+        };
+        run_with(matcher, &options, search)
+    }
+
+    pub fn search_path_fuzzy(
+        // every Java type is nullable, represented here as an Option<*type>
+        filename: *const c_char,
+        search_text: *const c_char,
+        max_distance: c_int,
+        options: *const SearchOptions,
+        result_callback: Option<SearchResultCallbackFn>,
+    ) -> Result<(), SearchStatusCode> {
+        use SearchStatusCode::*;
+
+        let options = parse_options(options)?;
+        let path = parse_path(filename)?;
+        let matcher = parse_fuzzy(search_text, max_distance, &options)?;
+        let callback = result_callback.ok_or(MissingCallback)?;
+
+        let search = ResultSearch {
+            path: &path,
+            search_text: ptr::null(),
+            options: &options,
+            callback,
+        };
+        run_with(matcher, &options, search)
+    }
+
+    pub fn search_path_snippet(
+        // every Java type is nullable, represented here as an Option<*type>
+        filename: *const c_char,
+        snippet: *const c_char,
+        options: *const SearchOptions,
+        result_callback: Option<SearchResultCallbackFn>,
+    ) -> Result<(), SearchStatusCode> {
+        use SearchStatusCode::*;
+
+        let options = parse_options(options)?;
+        let path = parse_path(filename)?;
+        let matcher = parse_snippet(snippet, &options)?;
+        let callback = result_callback.ok_or(MissingCallback)?;
+
+        // whitespace in the snippet can match line breaks, so matches can span lines
+        let options = ParsedOptions {
+            multi_line: true,
+            ..options
+        };
+        let search = ResultSearch {
+            path: &path,
+            search_text: ptr::null(),
+            options: &options,
+            callback,
+        };
+        run_with(matcher, &options, search)
+    }
+
+    pub fn search_path_files(
+        // every Java type is nullable, represented here as an Option<*type>
+        filename: *const c_char,
+        search_text: *const c_char,
+        options: *const SearchOptions,
+        mode: c_int,
+        path_callback: Option<FilePathCallbackFn>,
+    ) -> Result<(), SearchStatusCode> {
+        use SearchStatusCode::*;
+
+        let options = parse_options(options)?;
+        let mode = FileListMode::try_from(mode)?;
+        let path = parse_path(filename)?;
+        let matcher = parse_pattern(search_text, &options)?;
+        let callback = path_callback.ok_or(MissingCallback)?;
+
+        let listing = FileListing {
+            path: &path,
+            options: &options,
+            mode,
+            callback,
+        };
+        run_with_pattern(matcher, &options, listing)
+    }
+
+    pub fn search_path_exists(
+        // every Java type is nullable, represented here as an Option<*type>
+        filename: *const c_char,
+        search_text: *const c_char,
+        options: *const SearchOptions,
+    ) -> Result<bool, SearchStatusCode> {
+        let options = parse_options(options)?;
+        let path = parse_path(filename)?;
+        let matcher = parse_pattern(search_text, &options)?;
+
+        let finding = MatchFinding {
+            path: &path,
+            options: &options,
+        };
+        run_with_pattern(matcher, &options, finding)
+    }
+
+    pub fn pattern_validate(
+        search_text: *const c_char,
+        options: *const SearchOptions,
+        diagnostic: *mut PatternDiagnostic,
+    ) -> Result<(), SearchStatusCode> {
+        let validated = parse_options(options)
+            .map_err(|code| (code, None))
+            .and_then(|options| validate_pattern(search_text, &options));
+
+        // Java owns the diagnostic, so we only write into it
+        if let Some(diagnostic) = unsafe { diagnostic.as_mut() } {
+            let problem = validated
+                .as_ref()
+                .err()
+                .and_then(|(_, problem)| problem.as_ref());
+            let search_text = parse_search_text_lossy(search_text);
+            report_problem(diagnostic, &search_text, problem);
+        }
+
+        validated.map_err(|(code, _)| code)
+    }
+
+    fn report_problem(
+        diagnostic: &mut PatternDiagnostic,
+        search_text: &str,
+        problem: Option<&PatternProblem>,
+    ) {
+        let kind = problem.map(|p| p.kind).unwrap_or(PatternErrorKind::None);
+        let span = problem.and_then(|p| p.span);
+        let message = problem.map(|p| p.message.as_str()).unwrap_or("");
+
+        // Java indexes strings by UTF-16 code unit, so it needs the span in both units
+        let to_chars = |offset: usize| {
+            search_text
+                .get(..offset)
+                .map(|prefix| prefix.encode_utf16().count() as c_int)
+                .unwrap_or(-1)
+        };
+        diagnostic.kind = kind;
+        diagnostic.start_byte = span.map(|(start, _)| start as c_int).unwrap_or(-1);
+        diagnostic.end_byte = span.map(|(_, end)| end as c_int).unwrap_or(-1);
+        diagnostic.start_char = span.map(|(start, _)| to_chars(start)).unwrap_or(-1);
+        diagnostic.end_char = span.map(|(_, end)| to_chars(end)).unwrap_or(-1);
+        diagnostic.message_length = message.len() as c_int;
+
+        if diagnostic.message.is_null() || diagnostic.message_capacity <= 0 {
+            return;
+        }
+        // leave room for the nul-terminator, and don't cut a character in half
+        let mut length = message.len().min(diagnostic.message_capacity as usize - 1);
+        while !message.is_char_boundary(length) {
+            length -= 1;
+        }
+        unsafe {
+            ptr::copy_nonoverlapping(
+                message.as_ptr() as *const c_char,
+                diagnostic.message,
+                length,
+            );
+            *diagnostic.message.add(length) = 0;
+        }
+    }
+
+    // Configures ripgrep's line-oriented searcher from the search options
+    fn build_searcher(options: &ParsedOptions) -> Searcher {
+        let line_terminator = match options.line_terminator {
+            LineTerminatorKind::Crlf => LineTerminator::crlf(),
+            _ => LineTerminator::byte(options.line_terminator_byte()),
+        };
+
+        // counts don't need any of the lines around the matches
+        let counting = options.count != CountMode::None;
+        let context = |lines: u32| if counting { 0 } else { lines as usize };
+
+        SearcherBuilder::new()
+            .line_terminator(line_terminator)
+            .multi_line(options.multi_line)
+            .invert_match(options.invert_match)
+            .before_context(context(options.before_context))
+            .after_context(context(options.after_context))
+            .passthru(!counting && options.block_context != BlockContext::None)
+            .build()
+    }
+
+    // A search that can run with any kind of matcher, once the options have said which one to use
+    trait MatcherSearch {
+        type Output;
+
+        fn run<M: ResultMatcher>(self, matcher: M) -> Self::Output;
+    }
+
+    // Runs the search with the compiled pattern, whichever regex engine compiled it
+    fn run_with_pattern<S: MatcherSearch>(
+        matcher: PatternMatcher,
+        options: &ParsedOptions,
+        search: S,
+    ) -> S::Output {
+        match matcher {
+            PatternMatcher::RustRegex(matcher) => run_with(matcher, options, search),
+            #[cfg(feature = "pcre2")]
+            PatternMatcher::Pcre2(matcher) => run_with(matcher, options, search),
+        }
+    }
+
+    // Runs the search with the given matcher, or with one that normalizes the text first if the options ask for it
+    fn run_with<M: ResultMatcher, S: MatcherSearch>(
+        matcher: M,
+        options: &ParsedOptions,
+        search: S,
+    ) -> S::Output {
+        match options.normalizer() {
+            Some(normalization) => search.run(NormalizingMatcher::new(matcher, normalization)),
+            None => search.run(matcher),
+        }
+    }
+
+    // Calls back with every result under the path
+    struct ResultSearch<'a> {
+        path: &'a Path,
+        // the pattern, for the names of its capture groups, or null if it isn't a regex with any
+        search_text: *const c_char,
+        options: &'a ParsedOptions,
+        callback: SearchResultCallbackFn,
+    }
+
+    impl MatcherSearch for ResultSearch<'_> {
+        type Output = Result<(), SearchStatusCode>;
+
+        fn run<M: ResultMatcher>(self, matcher: M) -> Self::Output {
+            let capture_names = parse_capture_names(self.search_text, &matcher);
+            let record_start = parse_record_start(self.options)?;
+            let scope_headers = parse_scope_headers(self.options)?;
+            let context = SearchContext::new(
+                self.callback,
+                &matcher,
+                self.options,
+                capture_names,
+                record_start,
+                scope_headers,
+            );
+            search_parsed_path(self.path, &context)
+        }
+    }
+
+    // Calls back with the path of every file under the path that the listing is looking for
+    struct FileListing<'a> {
+        path: &'a Path,
+        options: &'a ParsedOptions,
+        mode: FileListMode,
+        callback: FilePathCallbackFn,
+    }
+
+    impl MatcherSearch for FileListing<'_> {
+        type Output = Result<(), SearchStatusCode>;
+
+        fn run<M: ResultMatcher>(self, matcher: M) -> Self::Output {
+            list_parsed_path(self.path, &matcher, self.options, self.mode, self.callback)
+        }
+    }
+
+    // Checks whether anything under the path matches
+    struct MatchFinding<'a> {
+        path: &'a Path,
+        options: &'a ParsedOptions,
+    }
+
+    impl MatcherSearch for MatchFinding<'_> {
+        type Output = Result<bool, SearchStatusCode>;
+
+        fn run<M: ResultMatcher>(self, matcher: M) -> Self::Output {
+            find_in_parsed_path(self.path, &matcher, self.options)
+        }
+    }
+
+    fn find_in_parsed_path<M: Matcher>(
+        path: &Path,
+        matcher: &M,
+        options: &ParsedOptions,
+    ) -> Result<bool, SearchStatusCode> {
+        use SearchStatusCode::*;
+
+        let mut searcher = build_searcher(options);
         match path {
-            file if file.is_file() => search_file(&file, matcher, callback),
-            dir if dir.is_dir() => search_dir(&dir, matcher, callback),
+            file if file.is_file() => has_match(file, matcher, &mut searcher),
+            dir if dir.is_dir() => {
+                let mut found = false;
+                // there's no need to search any more files once one of them has a match
+                search_dir(dir, |file| {
+                    found = has_match(file, matcher, &mut searcher)?;
+                    Ok(!found)
+                })?;
+                Ok(found)
+            }
             _ => Err(ErrorCouldNotOpenFile),
         }
     }
 
-    fn search_file(
+    // Searches the file only as far as its first match
+    fn has_match<M: Matcher>(
         file: &Path,
-        matcher: RegexMatcher,
-        callback: SearchResultCallbackFn,
+        matcher: &M,
+        searcher: &mut Searcher,
+    ) -> Result<bool, SearchStatusCode> {
+        let mut sink = FirstMatchSink::default();
+        searcher
+            .search_path(matcher, file, &mut sink)
+            .map_err(|e| e.status_code())?;
+        Ok(sink.found)
+    }
+
+    fn list_parsed_path<M: Matcher>(
+        path: &Path,
+        matcher: &M,
+        options: &ParsedOptions,
+        mode: FileListMode,
+        callback: FilePathCallbackFn,
     ) -> Result<(), SearchStatusCode> {
-        // the Sink type accepts search results from ripgrep
-        let sink = SearchResultCallbackSink(callback, file);
+        use SearchStatusCode::*;
 
-        Searcher::new()
-            .search_path(&matcher, file, sink)
-            .map(|_| ())
-            .map_err(|_| SearchStatusCode::ErrorFromCallback)
+        let mut searcher = build_searcher(options);
+        match path {
+            file if file.is_file() => list_file(file, matcher, mode, callback, &mut searcher),
+            dir if dir.is_dir() => search_dir(dir, |file| {
+                list_file(file, matcher, mode, callback, &mut searcher).map(|_| true)
+            }),
+            _ => Err(ErrorCouldNotOpenFile),
+        }
     }
 
+    // Calls back with the file's path, if it's one the listing is looking for
+    fn list_file<M: Matcher>(
+        file: &Path,
+        matcher: &M,
+        mode: FileListMode,
+        callback: FilePathCallbackFn,
+        searcher: &mut Searcher,
+    ) -> Result<(), SearchStatusCode> {
+        use SearchStatusCode::*;
+
+        let found = has_match(file, matcher, searcher)?;
+        let listed = match mode {
+            FileListMode::WithMatches => found,
+            FileListMode::WithoutMatch => !found,
+        };
+        if !listed {
+            return Ok(());
+        }
+
+        // unlike a result's file name, this one is nul-terminated, since it's all the callback gets
+        let path =
+            CString::new(file.to_string_lossy().as_bytes()).map_err(|_| ErrorCouldNotOpenFile)?;
+        if callback(path.as_ptr()) {
+            Ok(())
+        } else {
+            Err(ErrorFromCallback)
+        }
+    }
+
+    fn search_parsed_path<M: ResultMatcher>(
+        path: &Path,
+        context: &SearchContext<M>,
+    ) -> Result<(), SearchStatusCode> {
+        use SearchStatusCode::*;
+
+        let mut searcher = build_searcher(context.options);
+        match path {
+            file if file.is_file() => search_file(file, context, &mut searcher),
+            dir if dir.is_dir() => search_dir(dir, |file| {
+                search_file(file, context, &mut searcher).map(|_| true)
+            }),
+            _ => Err(ErrorCouldNotOpenFile),
+        }
+    }
+
+    fn search_file<M: ResultMatcher>(
+        file: &Path,
+        context: &SearchContext<M>,
+        searcher: &mut Searcher,
+    ) -> Result<(), SearchStatusCode> {
+        use SearchStatusCode::*;
+
+        // Finding the scope of a match means reading back through the lines before it, so it needs the whole file.
+        // So does multi-line mode, so the matcher can get the file ready once rather than after every match,
+        // and the searcher reads the whole file in that mode anyway.
+        let searched = if context.scope_headers.is_some() || context.options.multi_line {
+            let haystack = fs::read(file).map_err(|_| ErrorCouldNotOpenFile)?;
+            let sink = match context.scope_headers {
+                Some(_) => SearchResultCallbackSink::with_scope(context, file, &haystack),
+                None => SearchResultCallbackSink::new(context, file),
+            };
+            context.matcher.with_haystack(&haystack, || {
+                searcher.search_slice(context.matcher, &haystack, sink)
+            })
+        } else {
+            // the Sink type accepts search results from ripgrep
+            let sink = SearchResultCallbackSink::new(context, file);
+            searcher.search_path(context.matcher, file, sink)
+        };
+        searched.map_err(|e| e.status_code())?;
+
+        // running out of results isn't an error, but it ends the search all the same, even in a directory
+        if context.truncated.get() {
+            return Err(SuccessTruncated);
+        }
+        Ok(())
+    }
+
+    // Searches every file in the directory which isn't hidden, stopping at the first error,
+    // or as soon as searching a file returns false because there's no need to search any more
     fn search_dir(
         dir: &Path,
-        matcher: RegexMatcher,
-        callback: SearchResultCallbackFn,
+        mut search_file: impl FnMut(&Path) -> Result<bool, SearchStatusCode>,
     ) -> Result<(), SearchStatusCode> {
         use SearchStatusCode::*;
 
-        let walker = WalkDir::new(&dir).into_iter();
+        let walker = WalkDir::new(dir).into_iter();
         for entry in walker.filter_entry(|e| !is_hidden(e)) {
             let entry = entry.map_err(|_| ErrorCouldNotOpenFile)?;
 
@@ -100,16 +650,12 @@ mod core {
                 continue;
             }
 
-            // Pass cloned sink from the outer scope.
-            // This is probably fine, since we're just cloning a function pointer.
+            // Every file gets its own sink, sharing the context from the outer scope.
+            // This is probably fine, since the context only holds a function pointer and read-only state.
             // We'll trust our wrapper class to handle being called by multiple threads at once.
-            Searcher::new()
-                .search_path(
-                    &matcher,
-                    entry.path(),
-                    SearchResultCallbackSink(callback, entry.path()),
-                )
-                .map_err(|_| ErrorFromCallback)?;
+            if !search_file(entry.path())? {
+                break;
+            }
         }
         Ok(())
     }
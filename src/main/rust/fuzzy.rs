@@ -0,0 +1,202 @@
+// An approximate matcher, which finds a literal within a bounded number of edits.
+//
+// This is Sellers' algorithm: the usual dynamic programming table for the Levenshtein distance,
+// except that a match may start anywhere in the haystack instead of only at its beginning.
+// It takes time proportional to the pattern's length times the haystack's,
+// which is fine for the short names and identifiers this is meant for.
+// Distances are counted in characters, so substituting "é" for "e" is a single edit.
+use grep::matcher::{Match, Matcher, NoCaptures, NoError};
+
+#[derive(Clone, Debug)]
+pub struct FuzzyMatcher {
+    // the pattern split into characters, each as its UTF-8 bytes
+    pattern: Vec<Vec<u8>>,
+    max_distance: usize,
+}
+
+// One cell of the table: the fewest edits to match some prefix of the pattern ending at this point,
+// and where in the haystack that match starts
+#[derive(Clone, Copy, Debug)]
+struct Cell {
+    distance: usize,
+    start: usize,
+}
+
+impl FuzzyMatcher {
+    // The pattern must be longer than `max_distance`, or it would match anywhere,
+    // including as an empty match.
+    pub fn new(pattern: &[u8], max_distance: usize) -> FuzzyMatcher {
+        FuzzyMatcher {
+            pattern: characters(pattern).map(|c| c.to_vec()).collect(),
+            max_distance,
+        }
+    }
+
+    // The number of characters in the pattern
+    pub fn len(&self) -> usize {
+        self.pattern.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // Finds the first approximate match at or after `at`, along with its distance.
+    // Once a match is close enough, it's extended for as long as that brings its distance down,
+    // so an exact occurrence isn't cut short at the first prefix within the maximum distance.
+    pub fn find_fuzzy(&self, haystack: &[u8], at: usize) -> Option<(Match, usize)> {
+        let mut found: Option<(Match, usize)> = None;
+        self.scan(haystack, at, |cell, end| match found {
+            Some((_, distance)) if cell.distance >= distance => false,
+            _ if cell.distance <= self.max_distance => {
+                found = Some((Match::new(cell.start, end), cell.distance));
+                true
+            }
+            _ => true,
+        });
+        found
+    }
+
+    // The fewest edits needed to match the pattern anywhere in the haystack,
+    // as long as that's within the maximum distance
+    pub fn best_distance(&self, haystack: &[u8]) -> Option<usize> {
+        let mut best: Option<usize> = None;
+        self.scan(haystack, 0, |cell, _| {
+            best = Some(best.map_or(cell.distance, |b| b.min(cell.distance)));
+            // nothing can beat an exact match
+            cell.distance > 0
+        });
+        best.filter(|&distance| distance <= self.max_distance)
+    }
+
+    // Fills in the table one haystack character at a time, starting from `at`.
+    // After each character, `visit` gets the cell for the whole pattern and the offset just past that character,
+    // and the scan stops as soon as it returns false.
+    fn scan<F: FnMut(Cell, usize) -> bool>(&self, haystack: &[u8], at: usize, mut visit: F) {
+        // column[i] is the best match for the first i characters of the pattern, ending at `end`
+        let mut column: Vec<Cell> = (0..=self.len())
+            .map(|distance| Cell {
+                distance,
+                start: at,
+            })
+            .collect();
+
+        let mut end = at;
+        for character in characters(&haystack[at..]) {
+            end += character.len();
+            let mut diagonal = column[0];
+            // a match may start right after any character
+            column[0] = Cell {
+                distance: 0,
+                start: end,
+            };
+            for i in 1..column.len() {
+                let substituted = Cell {
+                    distance: diagonal.distance + (self.pattern[i - 1] != character) as usize,
+                    ..diagonal
+                };
+                let inserted = Cell {
+                    distance: column[i].distance + 1,
+                    ..column[i]
+                };
+                let deleted = Cell {
+                    distance: column[i - 1].distance + 1,
+                    ..column[i - 1]
+                };
+                diagonal = column[i];
+                column[i] = [substituted, inserted, deleted]
+                    .iter()
+                    .copied()
+                    .min_by_key(|cell| cell.distance)
+                    .unwrap_or(substituted);
+            }
+
+            if !visit(column[self.len()], end) {
+                return;
+            }
+        }
+    }
+}
+
+impl Matcher for FuzzyMatcher {
+    type Captures = NoCaptures;
+    type Error = NoError;
+
+    fn find_at(&self, haystack: &[u8], at: usize) -> Result<Option<Match>, NoError> {
+        Ok(self.find_fuzzy(haystack, at).map(|(m, _)| m))
+    }
+
+    fn new_captures(&self) -> Result<NoCaptures, NoError> {
+        Ok(NoCaptures::new())
+    }
+}
+
+// Splits text into its UTF-8 characters, treating each invalid byte as a character of its own
+fn characters(bytes: &[u8]) -> impl Iterator<Item = &[u8]> {
+    let mut rest = bytes;
+    std::iter::from_fn(move || {
+        let width = match rest.first()? {
+            0xF0..=0xF7 => 4,
+            0xE0..=0xEF => 3,
+            0xC0..=0xDF => 2,
+            _ => 1,
+        };
+        let width = match rest.get(..width).map(std::str::from_utf8) {
+            Some(Ok(_)) => width,
+            _ => 1,
+        };
+        let (character, tail) = rest.split_at(width);
+        rest = tail;
+        Some(character)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_finds_exact_occurrence_whole() {
+        let matcher = FuzzyMatcher::new(b"Benson", 1);
+        assert_eq!(
+            Some((Match::new(6, 12), 0)),
+            matcher.find_fuzzy(b"Barry Benson", 0)
+        );
+    }
+
+    #[test]
+    fn test_finds_occurrence_within_distance() {
+        let matcher = FuzzyMatcher::new(b"Benson", 2);
+        // one substitution and one deletion
+        assert_eq!(
+            Some((Match::new(6, 11), 2)),
+            matcher.find_fuzzy(b"Barry Bansn and Vanessa", 0)
+        );
+        assert_eq!(
+            None,
+            FuzzyMatcher::new(b"Benson", 1).find_fuzzy(b"Barry Bansn", 0)
+        );
+    }
+
+    #[test]
+    fn test_counts_edits_in_characters() {
+        let matcher = FuzzyMatcher::new("café".as_bytes(), 1);
+        assert_eq!(Some(1), matcher.best_distance(b"a cafe latte"));
+    }
+
+    #[test]
+    fn test_best_distance_looks_past_the_first_match() {
+        let matcher = FuzzyMatcher::new(b"Benson", 2);
+        assert_eq!(Some(0), matcher.best_distance(b"Bensen, not Benson"));
+        assert_eq!(None, matcher.best_distance(b"Vanessa Bloome"));
+    }
+
+    #[test]
+    fn test_find_at_reports_offsets_relative_to_whole_haystack() {
+        let matcher = FuzzyMatcher::new(b"bee", 0);
+        assert_eq!(
+            Some(Match::new(4, 7)),
+            matcher.find_at(b"bee bee", 1).unwrap()
+        );
+    }
+}
@@ -1,6 +1,12 @@
+extern crate aho_corasick;
 extern crate grep;
+extern crate regex_syntax;
+extern crate unicode_normalization;
+extern crate unicode_segmentation;
 
 pub use crate::ffi::*;
+pub use crate::fuzzy::*;
+pub use crate::literals::*;
 pub use crate::types::*;
 
 // Defines the actual Foreign Function Interface
@@ -12,6 +18,28 @@ mod types;
 // Handles parsing parameters passed to the library
 mod parse;
 
+// Matches large dictionaries of literal strings
+mod literals;
+
+// Matches a literal within a bounded number of edits
+mod fuzzy;
+
+// Searches with PCRE2, even in files that aren't valid UTF-8
+#[cfg(feature = "pcre2")]
+mod pcre2;
+
+// Converts byte offsets within a line into columns
+mod columns;
+
+// Normalizes Unicode text before it's searched
+mod normalize;
+
+// Translates other pattern dialects into regex syntax
+mod syntax;
+
+// Finds the method or class around each match
+mod scope;
+
 // Runs unit tests
 #[cfg(test)]
 mod tests;
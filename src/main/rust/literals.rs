@@ -0,0 +1,89 @@
+// A matcher for large dictionaries of literal strings.
+//
+// Compiling tens of thousands of literals into one big alternation with `RegexMatcher::new`
+// is slow and tends to run into the regex size limits,
+// so instead we build a single Aho-Corasick automaton over the whole dictionary.
+use aho_corasick::{AhoCorasick, AhoCorasickBuilder, MatchKind};
+use grep::matcher::{Match, Matcher, NoCaptures, NoError};
+
+#[derive(Clone, Debug)]
+pub struct LiteralSetMatcher {
+    automaton: AhoCorasick,
+}
+
+impl LiteralSetMatcher {
+    pub fn new<B: AsRef<[u8]>>(literals: &[B]) -> LiteralSetMatcher {
+        // leftmost-longest reports the longest dictionary entry starting at the earliest position,
+        // which is what a user expects when one entry is a prefix of another
+        let automaton = AhoCorasickBuilder::new()
+            .match_kind(MatchKind::LeftmostLongest)
+            .build(literals);
+        LiteralSetMatcher { automaton }
+    }
+
+    // The number of literals in the dictionary
+    pub fn len(&self) -> usize {
+        self.automaton.pattern_count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // Finds the first dictionary entry in the haystack,
+    // returning its index in the dictionary and where it matched
+    pub fn find_literal(&self, haystack: &[u8]) -> Option<(usize, Match)> {
+        self.automaton
+            .find(haystack)
+            .map(|m| (m.pattern(), Match::new(m.start(), m.end())))
+    }
+}
+
+impl Matcher for LiteralSetMatcher {
+    type Captures = NoCaptures;
+    type Error = NoError;
+
+    fn find_at(&self, haystack: &[u8], at: usize) -> Result<Option<Match>, NoError> {
+        // literals don't need any context before `at`, so we can just search the rest of the haystack
+        Ok(self
+            .find_literal(&haystack[at..])
+            .map(|(_, m)| m.offset(at)))
+    }
+
+    fn new_captures(&self) -> Result<NoCaptures, NoError> {
+        Ok(NoCaptures::new())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_finds_first_literal_in_haystack() {
+        let matcher = LiteralSetMatcher::new(&["bee", "honey", "pollen"]);
+        let found = matcher.find_literal(b"the pollen jocks make honey");
+        assert_eq!(Some((2, Match::new(4, 10))), found);
+    }
+
+    #[test]
+    fn test_prefers_longest_literal_at_same_position() {
+        let matcher = LiteralSetMatcher::new(&["bee", "beekeeper"]);
+        let found = matcher.find_literal(b"a beekeeper");
+        assert_eq!(Some((1, Match::new(2, 11))), found);
+    }
+
+    #[test]
+    fn test_find_at_reports_offsets_relative_to_whole_haystack() {
+        let matcher = LiteralSetMatcher::new(&["bee"]);
+        let found = matcher.find_at(b"bee bee", 1).unwrap();
+        assert_eq!(Some(Match::new(4, 7)), found);
+    }
+
+    #[test]
+    fn test_empty_dictionary_never_matches() {
+        let matcher = LiteralSetMatcher::new::<&str>(&[]);
+        assert!(matcher.is_empty());
+        assert_eq!(None, matcher.find(b"anything at all").unwrap());
+    }
+}
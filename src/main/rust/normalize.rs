@@ -0,0 +1,460 @@
+// Unicode normalization, so that text which looks the same matches the same.
+//
+// The pattern is normalized once before it's compiled, and every haystack is normalized before it's searched.
+// Normalizing can change the length of the text, so each haystack keeps a map back to its original offsets,
+// and every match is reported against the original bytes in the file.
+use std::cell::RefCell;
+use std::ops::Range;
+use std::rc::Rc;
+
+use grep::matcher::{Captures, Match, Matcher};
+use unicode_normalization::char::is_combining_mark;
+use unicode_normalization::UnicodeNormalization;
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::types::NormalizationForm;
+
+// How text gets normalized, from the search options
+#[derive(Clone, Copy, Debug)]
+pub struct Normalization {
+    pub form: NormalizationForm,
+    // strips accents and other combining marks, so "résumé" matches "resume"
+    pub fold_diacritics: bool,
+}
+
+impl Normalization {
+    pub fn normalize(&self, text: &str) -> String {
+        let compatible = self.form == NormalizationForm::Nfkc;
+        if self.fold_diacritics {
+            // the marks only come apart from the letters they're on after decomposing
+            let folded: String = if compatible {
+                text.nfkd().filter(|&c| !is_combining_mark(c)).collect()
+            } else {
+                text.nfd().filter(|&c| !is_combining_mark(c)).collect()
+            };
+            folded.nfc().collect()
+        } else if compatible {
+            text.nfkc().collect()
+        } else {
+            text.nfc().collect()
+        }
+    }
+
+    // Normalizes a haystack one grapheme cluster at a time, remembering where each one came from.
+    // Normalization never reaches across grapheme clusters, so they can be mapped back independently.
+    // Invalid UTF-8 is left as it is.
+    pub fn normalize_haystack(&self, haystack: &[u8]) -> NormalizedHaystack {
+        let mut normalized = NormalizedHaystack {
+            text: Vec::with_capacity(haystack.len()),
+            segments: Vec::new(),
+        };
+        let mut rest = haystack;
+        while !rest.is_empty() {
+            let (valid, invalid) = match std::str::from_utf8(rest) {
+                Ok(valid) => (valid, 0),
+                Err(e) => {
+                    let valid = std::str::from_utf8(&rest[..e.valid_up_to()]).unwrap_or("");
+                    (valid, e.error_len().unwrap_or(rest.len() - e.valid_up_to()))
+                }
+            };
+            for grapheme in valid.graphemes(true) {
+                let original_start = haystack.len() - rest.len();
+                rest = &rest[grapheme.len()..];
+                // ASCII never changes, which saves normalizing most of a typical file
+                if grapheme.is_ascii() {
+                    normalized.push_unchanged(grapheme.as_bytes(), original_start);
+                } else {
+                    normalized.push_normalized(self.normalize(grapheme).as_bytes(), original_start);
+                }
+            }
+            let original_start = haystack.len() - rest.len();
+            normalized.push_unchanged(&rest[..invalid], original_start);
+            rest = &rest[invalid..];
+        }
+        normalized.segments.push(Segment {
+            normalized_start: normalized.text.len(),
+            original_start: haystack.len(),
+            unchanged: false,
+        });
+        normalized
+    }
+}
+
+// A normalized haystack, along with where each part of it came from in the original
+#[derive(Debug)]
+pub struct NormalizedHaystack {
+    pub text: Vec<u8>,
+    // in order, and ending with an empty segment for the end of the haystack
+    segments: Vec<Segment>,
+}
+
+// A run of normalized text which came from a single grapheme cluster,
+// or from a run of text which normalizing didn't change at all
+#[derive(Clone, Copy, Debug)]
+struct Segment {
+    normalized_start: usize,
+    original_start: usize,
+    unchanged: bool,
+}
+
+impl NormalizedHaystack {
+    fn push_unchanged(&mut self, bytes: &[u8], original_start: usize) {
+        if bytes.is_empty() {
+            return;
+        }
+        // consecutive unchanged runs map back one-to-one, so they can share a segment
+        if !self.segments.last().is_some_and(|s| s.unchanged) {
+            self.segments.push(Segment {
+                normalized_start: self.text.len(),
+                original_start,
+                unchanged: true,
+            });
+        }
+        self.text.extend_from_slice(bytes);
+    }
+
+    fn push_normalized(&mut self, bytes: &[u8], original_start: usize) {
+        self.segments.push(Segment {
+            normalized_start: self.text.len(),
+            original_start,
+            unchanged: false,
+        });
+        self.text.extend_from_slice(bytes);
+    }
+
+    // Whether normalizing the original haystack from this offset, or up to it, gives the same text as here.
+    // That's true anywhere in text that normalizing didn't change, and between any two grapheme clusters.
+    fn is_boundary(&self, original: usize) -> bool {
+        let index = self
+            .segments
+            .partition_point(|s| s.original_start <= original)
+            .saturating_sub(1);
+        let segment = self.segments[index];
+        segment.unchanged || segment.original_start == original
+    }
+
+    // The offset in the normalized text of an offset in the original haystack
+    pub fn to_normalized(&self, original: usize) -> usize {
+        let index = self
+            .segments
+            .partition_point(|s| s.original_start <= original)
+            .saturating_sub(1);
+        let segment = self.segments[index];
+        if segment.unchanged {
+            segment.normalized_start + (original - segment.original_start)
+        } else {
+            segment.normalized_start
+        }
+    }
+
+    // The span in the original haystack of a match in the normalized text.
+    // A match that starts or ends partway through a grapheme cluster is widened to cover all of it.
+    pub fn to_original(&self, m: Match) -> Match {
+        let start_index = self
+            .segments
+            .partition_point(|s| s.normalized_start <= m.start())
+            .saturating_sub(1);
+        let start = self.segments[start_index];
+        let start = if start.unchanged {
+            start.original_start + (m.start() - start.normalized_start)
+        } else {
+            start.original_start
+        };
+
+        let end_index = self
+            .segments
+            .partition_point(|s| s.normalized_start < m.end())
+            .saturating_sub(1);
+        let end = self.segments[end_index];
+        let end = if end.unchanged || m.end() <= end.normalized_start {
+            end.original_start + (m.end() - end.normalized_start)
+        } else {
+            self.segments[end_index + 1].original_start
+        };
+        Match::new(start, end.max(start))
+    }
+}
+
+// The part of a normalized haystack that one search covers, with offsets relative to the start of that part
+struct NormalizedView {
+    normalized: Rc<NormalizedHaystack>,
+    original_start: usize,
+    range: Range<usize>,
+}
+
+impl NormalizedView {
+    fn text(&self) -> &[u8] {
+        &self.normalized.text[self.range.clone()]
+    }
+
+    fn to_normalized(&self, original: usize) -> usize {
+        self.normalized
+            .to_normalized(self.original_start + original)
+            - self.range.start
+    }
+
+    fn to_original(&self, m: Match) -> Match {
+        let m = self.normalized.to_original(m.offset(self.range.start));
+        Match::new(
+            m.start() - self.original_start,
+            m.end() - self.original_start,
+        )
+    }
+}
+
+// A whole haystack normalized ahead of time, along with the addresses of the bytes it came from
+#[derive(Clone, Debug)]
+struct PreparedHaystack {
+    addresses: Range<usize>,
+    normalized: Rc<NormalizedHaystack>,
+}
+
+// Wraps another matcher, so it searches normalized text but reports offsets in the original.
+// This doesn't pass on the wrapped matcher's line terminator, so the searcher hands it one line at a time.
+// In multi-line mode, though, the searcher searches the rest of the file again after every match,
+// so the whole file is prepared first, and each of those searches uses its part of the normalized file.
+#[derive(Clone, Debug)]
+pub struct NormalizingMatcher<M> {
+    inner: M,
+    normalization: Normalization,
+    prepared: RefCell<Option<PreparedHaystack>>,
+}
+
+impl<M: Matcher> NormalizingMatcher<M> {
+    pub fn new(inner: M, normalization: Normalization) -> NormalizingMatcher<M> {
+        NormalizingMatcher {
+            inner,
+            normalization,
+            prepared: RefCell::new(None),
+        }
+    }
+
+    // Normalizes the whole haystack, so searching any part of it while `search` runs doesn't normalize it again.
+    // `search` is given the normalized text, which each search of a part of the haystack searches part of.
+    pub fn with_prepared<T>(&self, haystack: &[u8], search: impl FnOnce(&[u8]) -> T) -> T {
+        let start = haystack.as_ptr() as usize;
+        let normalized = Rc::new(self.normalize(haystack));
+        *self.prepared.borrow_mut() = Some(PreparedHaystack {
+            addresses: start..start + haystack.len(),
+            normalized: Rc::clone(&normalized),
+        });
+        let searched = search(&normalized.text);
+        // the haystack may be gone after this, and something else may take its place at the same address
+        *self.prepared.borrow_mut() = None;
+        searched
+    }
+
+    // The normalized haystack, from the prepared one if it's part of it
+    fn view(&self, haystack: &[u8]) -> NormalizedView {
+        let start = haystack.as_ptr() as usize;
+        if let Some(prepared) = self.prepared.borrow().as_ref() {
+            let addresses = &prepared.addresses;
+            if addresses.start <= start && start + haystack.len() <= addresses.end {
+                let normalized = &prepared.normalized;
+                let original = start - addresses.start..start - addresses.start + haystack.len();
+                if normalized.is_boundary(original.start) && normalized.is_boundary(original.end) {
+                    return NormalizedView {
+                        normalized: Rc::clone(normalized),
+                        original_start: original.start,
+                        range: normalized.to_normalized(original.start)
+                            ..normalized.to_normalized(original.end),
+                    };
+                }
+            }
+        }
+        let normalized = self.normalize(haystack);
+        NormalizedView {
+            range: 0..normalized.text.len(),
+            normalized: Rc::new(normalized),
+            original_start: 0,
+        }
+    }
+
+    pub fn inner(&self) -> &M {
+        &self.inner
+    }
+
+    pub fn normalize(&self, haystack: &[u8]) -> NormalizedHaystack {
+        self.normalization.normalize_haystack(haystack)
+    }
+}
+
+// The capture groups of the wrapped matcher, moved back onto the original haystack
+#[derive(Clone, Debug, Default)]
+pub struct NormalizedCaptures {
+    groups: Vec<Option<Match>>,
+}
+
+impl NormalizedCaptures {
+    fn set_from<C: Captures>(&mut self, captures: &C, normalized: &NormalizedView) {
+        self.groups.clear();
+        self.groups.extend(
+            (0..captures.len()).map(|i| captures.get(i).map(|m| normalized.to_original(m))),
+        );
+    }
+}
+
+impl Captures for NormalizedCaptures {
+    fn len(&self) -> usize {
+        self.groups.len()
+    }
+
+    fn get(&self, i: usize) -> Option<Match> {
+        self.groups.get(i).copied().flatten()
+    }
+}
+
+impl<M: Matcher> Matcher for NormalizingMatcher<M> {
+    type Captures = NormalizedCaptures;
+    type Error = M::Error;
+
+    fn find_at(&self, haystack: &[u8], at: usize) -> Result<Option<Match>, M::Error> {
+        let normalized = self.view(haystack);
+        let found = self
+            .inner
+            .find_at(normalized.text(), normalized.to_normalized(at))?;
+        Ok(found.map(|m| normalized.to_original(m)))
+    }
+
+    fn new_captures(&self) -> Result<NormalizedCaptures, M::Error> {
+        Ok(NormalizedCaptures::default())
+    }
+
+    fn capture_count(&self) -> usize {
+        self.inner.capture_count()
+    }
+
+    fn capture_index(&self, name: &str) -> Option<usize> {
+        self.inner.capture_index(name)
+    }
+
+    // the iterating methods normalize the haystack once, instead of once per match
+    fn try_find_iter<F, E>(
+        &self,
+        haystack: &[u8],
+        mut matched: F,
+    ) -> Result<Result<(), E>, M::Error>
+    where
+        F: FnMut(Match) -> Result<bool, E>,
+    {
+        let normalized = self.view(haystack);
+        self.inner
+            .try_find_iter(normalized.text(), |m| matched(normalized.to_original(m)))
+    }
+
+    fn captures_at(
+        &self,
+        haystack: &[u8],
+        at: usize,
+        caps: &mut NormalizedCaptures,
+    ) -> Result<bool, M::Error> {
+        let normalized = self.view(haystack);
+        let mut inner_caps = self.inner.new_captures()?;
+        let found = self.inner.captures_at(
+            normalized.text(),
+            normalized.to_normalized(at),
+            &mut inner_caps,
+        )?;
+        if found {
+            caps.set_from(&inner_caps, &normalized);
+        }
+        Ok(found)
+    }
+
+    fn try_captures_iter<F, E>(
+        &self,
+        haystack: &[u8],
+        caps: &mut NormalizedCaptures,
+        mut matched: F,
+    ) -> Result<Result<(), E>, M::Error>
+    where
+        F: FnMut(&NormalizedCaptures) -> Result<bool, E>,
+    {
+        let normalized = self.view(haystack);
+        let mut inner_caps = self.inner.new_captures()?;
+        self.inner
+            .try_captures_iter(normalized.text(), &mut inner_caps, |inner_caps| {
+                caps.set_from(inner_caps, &normalized);
+                matched(caps)
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use grep::regex::RegexMatcher;
+
+    use super::*;
+
+    const NFC: Normalization = Normalization {
+        form: NormalizationForm::Nfc,
+        fold_diacritics: false,
+    };
+    const FOLDED: Normalization = Normalization {
+        form: NormalizationForm::Nfc,
+        fold_diacritics: true,
+    };
+
+    #[test]
+    fn test_decomposed_text_is_composed() {
+        assert_eq!("r\u{e9}sum\u{e9}", NFC.normalize("re\u{301}sume\u{301}"));
+    }
+
+    #[test]
+    fn test_folding_strips_diacritics() {
+        assert_eq!("resume", FOLDED.normalize("r\u{e9}sume\u{301}"));
+    }
+
+    #[test]
+    fn test_compatibility_form_expands_ligatures() {
+        let nfkc = Normalization {
+            form: NormalizationForm::Nfkc,
+            fold_diacritics: false,
+        };
+        assert_eq!("office", nfkc.normalize("o\u{fb03}ce"));
+    }
+
+    #[test]
+    fn test_matches_map_back_to_original_offsets() {
+        // "e" plus a combining accent is 3 bytes, but folds to the single byte "e"
+        let haystack = "my re\u{301}sume\u{301}!".as_bytes();
+        let normalized = FOLDED.normalize_haystack(haystack);
+        assert_eq!(b"my resume!".to_vec(), normalized.text);
+        assert_eq!(Match::new(3, 13), normalized.to_original(Match::new(3, 9)));
+        assert_eq!(
+            Match::new(13, 14),
+            normalized.to_original(Match::new(9, 10))
+        );
+        assert_eq!(9, normalized.to_normalized(13));
+    }
+
+    #[test]
+    fn test_invalid_utf8_is_left_alone() {
+        let haystack = b"caf\xff\xc3\xa9";
+        let normalized = FOLDED.normalize_haystack(haystack);
+        assert_eq!(b"caf\xffe".to_vec(), normalized.text);
+        assert_eq!(Match::new(4, 6), normalized.to_original(Match::new(4, 5)));
+    }
+
+    #[test]
+    fn test_searching_part_of_prepared_haystack_uses_its_normalized_text() {
+        let matcher = NormalizingMatcher::new(RegexMatcher::new("r\u{e9}sum\u{e9}").unwrap(), NFC);
+        let haystack = "re\u{301}sume\u{301}\nsend your re\u{301}sume\u{301}\n".as_bytes();
+        let rest = &haystack[11..];
+
+        let unprepared = matcher.find(rest).unwrap();
+        let prepared = matcher.with_prepared(haystack, |_| {
+            let view = matcher.view(rest);
+            let prepared = matcher.prepared.borrow();
+            assert!(Rc::ptr_eq(
+                &view.normalized,
+                &prepared.as_ref().unwrap().normalized
+            ));
+            matcher.find(rest).unwrap()
+        });
+
+        assert_eq!(Some(Match::new(10, 20)), unprepared);
+        assert_eq!(unprepared, prepared);
+        assert!(matcher.prepared.borrow().is_none());
+    }
+}
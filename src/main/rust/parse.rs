@@ -1,10 +1,20 @@
-use std::ffi::CStr;
-use std::os::raw::c_char;
+use std::convert::TryFrom;
+use std::ffi::{CStr, CString};
+use std::os::raw::{c_char, c_int};
 use std::path::PathBuf;
+use std::slice;
 use std::str::{from_utf8, Utf8Error};
 
-use grep::regex::RegexMatcher;
+use grep::matcher::Matcher;
+use grep::regex::{self, RegexMatcher, RegexMatcherBuilder};
+use regex_syntax::ast;
 
+use crate::fuzzy::FuzzyMatcher;
+use crate::literals::LiteralSetMatcher;
+#[cfg(feature = "pcre2")]
+use crate::pcre2::Pcre2Matcher;
+use crate::scope::ScopeHeaders;
+use crate::syntax::translate;
 use crate::types::*;
 
 /// Convert a native string to a Rust string
@@ -14,6 +24,15 @@ fn to_string(pointer: *const c_char) -> Result<String, Utf8Error> {
     from_utf8(slice).map(|s| s.to_string())
 }
 
+// Reads a native string for display, even if it isn't valid UTF-8
+pub fn parse_search_text_lossy(search_text: *const c_char) -> String {
+    if search_text.is_null() {
+        return String::new();
+    }
+    let cstr = unsafe { CStr::from_ptr(search_text) };
+    cstr.to_string_lossy().into_owned()
+}
+
 // Either finds the Path with the given name, or returns an error code to pass out of the library
 pub fn parse_path(filename: *const c_char) -> Result<PathBuf, SearchStatusCode> {
     use SearchStatusCode::*;
@@ -37,7 +56,10 @@ pub fn parse_path(filename: *const c_char) -> Result<PathBuf, SearchStatusCode>
 
 // Either generates a regular-expression matcher from the given C-style string,
 // or returns an error code to pass out of the library
-pub fn parse_search_text(search_text: *const c_char) -> Result<RegexMatcher, SearchStatusCode> {
+pub fn parse_search_text(
+    search_text: *const c_char,
+    options: &ParsedOptions,
+) -> Result<RegexMatcher, SearchStatusCode> {
     use SearchStatusCode::*;
 
     // Java owns the string, so we view the text as a &CStr reference rather than an owned CString
@@ -46,14 +68,534 @@ pub fn parse_search_text(search_text: *const c_char) -> Result<RegexMatcher, Sea
     }
 
     let search_text: String = match to_string(search_text) {
-        Ok(search_text) => search_text,
+        Ok(search_text) => prepare_pattern(search_text, options),
         Err(_) => return Err(ErrorBadPattern),
     };
 
-    match RegexMatcher::new(&search_text) {
-        Ok(regex) => Ok(regex),
+    build_regex(&search_text, options)
+        .map_err(|error| diagnose_regex_error(&search_text, options, &error).status_code())
+}
+
+// Puts literal text in the same normal form as the text it'll be searching, if the options ask for one
+fn normalize_literal(literal: String, options: &ParsedOptions) -> String {
+    match options.normalizer() {
+        Some(normalization) => normalization.normalize(&literal),
+        None => literal,
+    }
+}
+
+// Puts the literal text of a regex in the same normal form as the text it'll be searching, if the options ask for one.
+// A character whose normal form is a metacharacter, like the fullwidth `（`, is still matched literally.
+fn normalize_regex(regex: String, options: &ParsedOptions) -> String {
+    let normalization = match options.normalizer() {
+        Some(normalization) => normalization,
+        None => return regex,
+    };
+    // metacharacters never combine with the characters around them, so the text between them is normalized on its own
+    let mut normalized = String::with_capacity(regex.len());
+    let mut literal_start = 0;
+    for (i, c) in regex.char_indices() {
+        if regex_syntax::is_meta_character(c) {
+            push_escaped(
+                &mut normalized,
+                &normalization.normalize(&regex[literal_start..i]),
+            );
+            normalized.push(c);
+            literal_start = i + c.len_utf8();
+        }
+    }
+    push_escaped(
+        &mut normalized,
+        &normalization.normalize(&regex[literal_start..]),
+    );
+    normalized
+}
+
+fn push_escaped(regex: &mut String, literal: &str) {
+    for c in literal.chars() {
+        if regex_syntax::is_meta_character(c) {
+            regex.push('\\');
+        }
+        regex.push(c);
+    }
+}
+
+// Turns the search text into the regex either engine compiles, whatever dialect it's written in
+fn prepare_pattern(search_text: String, options: &ParsedOptions) -> String {
+    let regex = translate(&search_text, options.syntax);
+    normalize_regex(regex, options)
+}
+
+// Compiles the search text with the default regex engine, configured from the search options
+fn build_regex(search_text: &str, options: &ParsedOptions) -> Result<RegexMatcher, regex::Error> {
+    // `^` and `$` always anchor at line boundaries, like they do in ripgrep
+    let mut builder = RegexMatcherBuilder::new();
+    builder
+        .multi_line(true)
+        .dot_matches_new_line(options.multi_line && options.dot_matches_new_line);
+    let is_crlf = options.line_terminator == LineTerminatorKind::Crlf;
+    if options.multi_line {
+        // matches may span lines, so the matcher can't promise never to match a line terminator
+        builder.crlf(is_crlf).line_terminator(None);
+    } else if is_crlf {
+        builder.crlf(true);
+    } else {
+        // this keeps classes like `\s` and `[^a]` from matching across records
+        builder.line_terminator(Some(options.line_terminator_byte()));
+    }
+    builder.unicode(!options.disable_unicode);
+    if options.regex_size_limit > 0 {
+        builder.size_limit(to_usize(options.regex_size_limit));
+    }
+    if options.dfa_size_limit > 0 {
+        builder.dfa_size_limit(to_usize(options.dfa_size_limit));
+    }
+    if options.nest_limit > 0 {
+        builder.nest_limit(options.nest_limit);
+    }
+
+    builder.build(search_text)
+}
+
+// Limits larger than the address space can't be reached anyway
+fn to_usize(limit: u64) -> usize {
+    usize::try_from(limit).unwrap_or(usize::MAX)
+}
+
+// What was wrong with a pattern the library rejected
+#[derive(Clone, Debug)]
+pub struct PatternProblem {
+    pub kind: PatternErrorKind,
+    // the byte range of the offending syntax, when it can be pinned down
+    pub span: Option<(usize, usize)>,
+    pub message: String,
+}
+
+impl PatternProblem {
+    fn new(kind: PatternErrorKind, span: Option<(usize, usize)>, message: String) -> Self {
+        PatternProblem {
+            kind,
+            span,
+            message,
+        }
+    }
+
+    // Any span was found in the pattern after it was normalized and translated into regex syntax,
+    // so it's only kept if the pattern as typed is the same up to the end of it
+    fn in_typed_pattern(self, typed: &str, prepared: &str) -> Self {
+        let span = self
+            .span
+            .filter(|&(_, end)| typed.as_bytes().get(..end) == prepared.as_bytes().get(..end));
+        PatternProblem { span, ..self }
+    }
+
+    pub fn status_code(&self) -> SearchStatusCode {
+        match self.kind {
+            PatternErrorKind::TooBig => SearchStatusCode::ErrorPatternTooBig,
+            _ => SearchStatusCode::ErrorBadPattern,
+        }
+    }
+}
+
+// Works out why the default regex engine rejected a pattern.
+// grep-regex only reports its errors as strings, so we re-parse the pattern with regex-syntax
+// to find out what's wrong and where.
+fn diagnose_regex_error(
+    search_text: &str,
+    options: &ParsedOptions,
+    error: &regex::Error,
+) -> PatternProblem {
+    let mut parser = ast::parse::ParserBuilder::new();
+    if options.nest_limit > 0 {
+        parser.nest_limit(options.nest_limit);
+    }
+    if let Err(e) = parser.build().parse(search_text) {
+        let kind = match e.kind() {
+            ast::ErrorKind::NestLimitExceeded(_) => PatternErrorKind::TooBig,
+            ast::ErrorKind::UnsupportedBackreference | ast::ErrorKind::UnsupportedLookAround => {
+                PatternErrorKind::Unsupported
+            }
+            _ => PatternErrorKind::Syntax,
+        };
+        let span = (e.span().start.offset, e.span().end.offset);
+        return PatternProblem::new(kind, Some(span), e.kind().to_string());
+    }
+
+    let translated = regex_syntax::ParserBuilder::new()
+        .allow_invalid_utf8(true)
+        .unicode(!options.disable_unicode)
+        .build()
+        .parse(search_text);
+    if let Err(regex_syntax::Error::Translate(e)) = translated {
+        let span = (e.span().start.offset, e.span().end.offset);
+        return PatternProblem::new(PatternErrorKind::Syntax, Some(span), e.kind().to_string());
+    }
+
+    match error.kind() {
+        // the pattern itself is fine, so compiling it must have gone over the size limit
+        regex::ErrorKind::Regex(message) => {
+            PatternProblem::new(PatternErrorKind::TooBig, None, message.clone())
+        }
+        regex::ErrorKind::NotAllowed(_) => {
+            PatternProblem::new(PatternErrorKind::NotAllowed, None, error.to_string())
+        }
+        _ => PatternProblem::new(PatternErrorKind::Syntax, None, error.to_string()),
+    }
+}
+
+// Reads the search options passed to the library, using the defaults when none were given,
+// or returns an error code if any enum or flag has a value it can't take
+pub fn parse_options(options: *const SearchOptions) -> Result<ParsedOptions, SearchStatusCode> {
+    // Java owns the struct, so we copy it rather than holding on to the pointer
+    let options = if options.is_null() {
+        SearchOptions::default()
+    } else {
+        unsafe { *options }
+    };
+    Ok(ParsedOptions {
+        engine: RegexEngine::try_from(options.engine)?,
+        multi_line: parse_flag(options.multi_line)?,
+        dot_matches_new_line: parse_flag(options.dot_matches_new_line)?,
+        invert_match: parse_flag(options.invert_match)?,
+        line_terminator: LineTerminatorKind::try_from(options.line_terminator)?,
+        line_terminator_byte: options.line_terminator_byte,
+        regex_size_limit: options.regex_size_limit,
+        dfa_size_limit: options.dfa_size_limit,
+        nest_limit: options.nest_limit,
+        disable_unicode: parse_flag(options.disable_unicode)?,
+        report_captures: parse_flag(options.report_captures)?,
+        report_match_spans: parse_flag(options.report_match_spans)?,
+        column_unit: ColumnUnit::try_from(options.column_unit)?,
+        tab_width: options.tab_width,
+        only_matching: parse_flag(options.only_matching)?,
+        normalization: NormalizationForm::try_from(options.normalization)?,
+        fold_diacritics: parse_flag(options.fold_diacritics)?,
+        syntax: PatternSyntax::try_from(options.syntax)?,
+        before_context: options.before_context,
+        after_context: options.after_context,
+        block_context: BlockContext::try_from(options.block_context)?,
+        record_start: options.record_start,
+        scope_language: ScopeLanguage::try_from(options.scope_language)?,
+        scope_header: options.scope_header,
+        count: CountMode::try_from(options.count)?,
+        max_count: options.max_count,
+        report_file_end: parse_flag(options.report_file_end)?,
+        max_results: options.max_results,
+        max_columns: options.max_columns,
+        preview_each_match: parse_flag(options.preview_each_match)?,
+        strip_line_terminator: parse_flag(options.strip_line_terminator)?,
+        trim: parse_flag(options.trim)?,
+    })
+}
+
+// Reads a flag, which Java passes as a `c_int` of 0 or 1
+fn parse_flag(flag: c_int) -> Result<bool, SearchStatusCode> {
+    match flag {
+        0 => Ok(false),
+        1 => Ok(true),
+        _ => Err(SearchStatusCode::InvalidOptions),
+    }
+}
+
+// Either compiles the C-style search text with the regex engine chosen in the options,
+// or returns an error code to pass out of the library
+pub fn parse_pattern(
+    search_text: *const c_char,
+    options: &ParsedOptions,
+) -> Result<PatternMatcher, SearchStatusCode> {
+    use SearchStatusCode::*;
+
+    match options.engine {
+        RegexEngine::Default => {
+            parse_search_text(search_text, options).map(PatternMatcher::RustRegex)
+        }
+        RegexEngine::Pcre2 => parse_pcre2_search_text(search_text, options),
+        RegexEngine::Auto => match parse_search_text(search_text, options) {
+            Ok(regex) => Ok(PatternMatcher::RustRegex(regex)),
+            // only fall back for patterns the default engine rejects, and only if PCRE2 is built in
+            Err(ErrorBadPattern) => {
+                parse_pcre2_search_text(search_text, options).map_err(|code| match code {
+                    ErrorEngineUnavailable => ErrorBadPattern,
+                    code => code,
+                })
+            }
+            Err(code) => Err(code),
+        },
+    }
+}
+
+#[cfg(feature = "pcre2")]
+fn parse_pcre2_search_text(
+    search_text: *const c_char,
+    options: &ParsedOptions,
+) -> Result<PatternMatcher, SearchStatusCode> {
+    use SearchStatusCode::*;
+
+    if search_text.is_null() {
+        return Err(MissingSearchText);
+    }
+
+    let search_text: String = match to_string(search_text) {
+        Ok(search_text) => prepare_pattern(search_text, options),
         Err(_) => return Err(ErrorBadPattern),
+    };
+
+    build_pcre2(&search_text, options)
+        .map(PatternMatcher::Pcre2)
+        .map_err(|_| ErrorBadPattern)
+}
+
+#[cfg(feature = "pcre2")]
+fn build_pcre2(
+    search_text: &str,
+    options: &ParsedOptions,
+) -> Result<Pcre2Matcher, grep::pcre2::Error> {
+    // Unless Unicode is off, the matcher adds UTF and Unicode-property modes where it can,
+    // which give classes like \w the same meaning they have in Java.
+    // PCRE2 can't be told about a custom line terminator, so outside multi-line mode,
+    // the searcher hands it one record at a time without its terminator.
+    let mut builder = grep::pcre2::RegexMatcherBuilder::new();
+    builder
+        .multi_line(true)
+        .dotall(options.multi_line && options.dot_matches_new_line)
+        .crlf(options.line_terminator == LineTerminatorKind::Crlf)
+        .jit_if_available(true);
+    Pcre2Matcher::new(&builder, search_text, !options.disable_unicode)
+}
+
+#[cfg(not(feature = "pcre2"))]
+fn parse_pcre2_search_text(
+    search_text: *const c_char,
+    _options: &ParsedOptions,
+) -> Result<PatternMatcher, SearchStatusCode> {
+    use SearchStatusCode::*;
+
+    if search_text.is_null() {
+        return Err(MissingSearchText);
+    }
+    Err(ErrorEngineUnavailable)
+}
+
+// Checks whether the C-style search text would compile with the options given, without searching.
+// Patterns that are rejected come with a description of the problem, when there is one.
+pub fn validate_pattern(
+    search_text: *const c_char,
+    options: &ParsedOptions,
+) -> Result<(), (SearchStatusCode, Option<PatternProblem>)> {
+    use SearchStatusCode::*;
+
+    match parse_pattern(search_text, options) {
+        Ok(_) => Ok(()),
+        Err(code @ ErrorBadPattern) | Err(code @ ErrorPatternTooBig) => {
+            let problem = match to_string(search_text) {
+                Ok(search_text) => {
+                    let prepared = prepare_pattern(search_text.clone(), options);
+                    diagnose_pattern(&prepared, options)
+                        .map(|problem| problem.in_typed_pattern(&search_text, &prepared))
+                }
+                Err(e) => {
+                    let end = e.valid_up_to();
+                    let message = "the pattern is not valid UTF-8".to_string();
+                    Some(PatternProblem::new(
+                        PatternErrorKind::Syntax,
+                        Some((end, end)),
+                        message,
+                    ))
+                }
+            };
+            Err((code, problem))
+        }
+        Err(code) => Err((code, None)),
+    }
+}
+
+fn diagnose_pattern(search_text: &str, options: &ParsedOptions) -> Option<PatternProblem> {
+    #[cfg(feature = "pcre2")]
+    {
+        if options.engine == RegexEngine::Pcre2 {
+            let error = build_pcre2(search_text, options).err()?;
+            return Some(PatternProblem::new(
+                PatternErrorKind::Syntax,
+                None,
+                error.to_string(),
+            ));
+        }
+    }
+
+    // the auto engine falls back to PCRE2, but its patterns are written for the default engine first
+    let error = build_regex(search_text, options).err()?;
+    Some(diagnose_regex_error(search_text, options, &error))
+}
+
+// Finds the names of the compiled pattern's capture groups, indexed by group number.
+// Unnamed groups have no name.
+pub fn parse_capture_names<M: Matcher>(
+    search_text: *const c_char,
+    matcher: &M,
+) -> Vec<Option<CString>> {
+    let mut names = vec![None; matcher.capture_count()];
+    let search_text = parse_search_text_lossy(search_text);
+    for name in candidate_capture_names(&search_text) {
+        if let Some(index) = matcher.capture_index(name) {
+            if let Some(slot) = names.get_mut(index) {
+                *slot = CString::new(name).ok();
+            }
+        }
+    }
+    names
+}
+
+// Finds everything that looks like a group name, in any of the syntaxes `(?P<name>`, `(?<name>` or `(?'name'`.
+// Neither regex engine lists its group names, but both can look one up,
+// so the matcher gets the final say on which of these really are groups.
+fn candidate_capture_names(search_text: &str) -> impl Iterator<Item = &str> {
+    search_text
+        .match_indices("(?")
+        .filter_map(move |(start, _)| {
+            let rest = &search_text[start + 2..];
+            let (rest, close) = if let Some(rest) = rest.strip_prefix("P<") {
+                (rest, '>')
+            } else if let Some(rest) = rest.strip_prefix('<') {
+                (rest, '>')
+            } else if let Some(rest) = rest.strip_prefix('\'') {
+                (rest, '\'')
+            } else {
+                return None;
+            };
+            let name = &rest[..rest.find(close)?];
+            let is_name = !name.is_empty() && name.chars().all(|c| c.is_alphanumeric() || c == '_');
+            if is_name {
+                Some(name)
+            } else {
+                None
+            }
+        })
+}
+
+// Either builds a dictionary matcher from the given array of C-style strings,
+// or returns an error code to pass out of the library
+pub fn parse_literals(
+    literals: *const *const c_char,
+    num_literals: c_int,
+    options: &ParsedOptions,
+) -> Result<LiteralSetMatcher, SearchStatusCode> {
+    use SearchStatusCode::*;
+
+    if literals.is_null() || num_literals < 0 {
+        return Err(MissingSearchText);
+    }
+
+    // Java owns the array and every string in it, so we only ever borrow them
+    let pointers = unsafe { slice::from_raw_parts(literals, num_literals as usize) };
+    let mut dictionary = Vec::with_capacity(pointers.len());
+    for &pointer in pointers {
+        if pointer.is_null() {
+            return Err(MissingSearchText);
+        }
+        let literal = to_string(pointer).map_err(|_| ErrorBadPattern)?;
+        let literal = normalize_literal(literal, options);
+        // an empty literal would match everywhere, and one spanning lines could never be reported as a single line
+        if literal.is_empty() || literal.as_bytes().contains(&options.line_terminator_byte()) {
+            return Err(ErrorBadPattern);
+        }
+        dictionary.push(literal);
+    }
+
+    Ok(LiteralSetMatcher::new(&dictionary))
+}
+
+// Builds an approximate matcher for a literal, which must be longer than the number of edits allowed
+pub fn parse_fuzzy(
+    search_text: *const c_char,
+    max_distance: c_int,
+    options: &ParsedOptions,
+) -> Result<FuzzyMatcher, SearchStatusCode> {
+    use SearchStatusCode::*;
+
+    if search_text.is_null() {
+        return Err(MissingSearchText);
+    }
+    let literal = to_string(search_text).map_err(|_| ErrorBadPattern)?;
+    let literal = normalize_literal(literal, options);
+    // a literal spanning lines could never be reported as a single matching line
+    if literal.as_bytes().contains(&options.line_terminator_byte()) {
+        return Err(ErrorBadPattern);
+    }
+    // with that many edits, every line would match
+    if max_distance < 0 || max_distance as usize >= literal.chars().count() {
+        return Err(ErrorBadPattern);
+    }
+
+    Ok(FuzzyMatcher::new(literal.as_bytes(), max_distance as usize))
+}
+
+// Compiles the regex for the first line of each record, when matches are reported with their whole record
+pub fn parse_record_start(
+    options: &ParsedOptions,
+) -> Result<Option<RegexMatcher>, SearchStatusCode> {
+    if options.block_context != BlockContext::Record {
+        return Ok(None);
+    }
+    // it's matched against the original lines one at a time, so none of the pattern options apply to it
+    let line_options = ParsedOptions {
+        multi_line: false,
+        normalization: NormalizationForm::None,
+        fold_diacritics: false,
+        syntax: PatternSyntax::Regex,
+        ..*options
+    };
+    parse_search_text(options.record_start, &line_options).map(Some)
+}
+
+// Compiles the regex for scope header lines, or picks a language's built-in headers
+pub fn parse_scope_headers(
+    options: &ParsedOptions,
+) -> Result<Option<ScopeHeaders>, SearchStatusCode> {
+    if options.scope_header.is_null() {
+        return Ok(ScopeHeaders::for_language(options.scope_language));
+    }
+    // like a record start, it's matched against the original lines one at a time
+    let line_options = ParsedOptions {
+        multi_line: false,
+        normalization: NormalizationForm::None,
+        fold_diacritics: false,
+        syntax: PatternSyntax::Regex,
+        ..*options
+    };
+    parse_search_text(options.scope_header, &line_options)
+        .map(|header| Some(ScopeHeaders::new(header)))
+}
+
+// Turns a pasted snippet of text into a regex where every run of whitespace matches any run of whitespace,
+// including line breaks, so it still finds the snippet after it's been reindented or rewrapped.
+// The regex has to be searched in multi-line mode.
+pub fn parse_snippet(
+    search_text: *const c_char,
+    options: &ParsedOptions,
+) -> Result<RegexMatcher, SearchStatusCode> {
+    use SearchStatusCode::*;
+
+    if search_text.is_null() {
+        return Err(MissingSearchText);
     }
+    let snippet = to_string(search_text).map_err(|_| ErrorBadPattern)?;
+    let snippet = normalize_literal(snippet, options);
+    let words: Vec<String> = snippet
+        .split_whitespace()
+        .map(regex_syntax::escape)
+        .collect();
+    // a snippet of nothing but whitespace would match everywhere
+    if words.is_empty() {
+        return Err(ErrorBadPattern);
+    }
+
+    let options = ParsedOptions {
+        multi_line: true,
+        ..*options
+    };
+    let pattern = words.join(r"\s+");
+    build_regex(&pattern, &options)
+        .map_err(|error| diagnose_regex_error(&pattern, &options, &error).status_code())
 }
 
 #[cfg(test)]
@@ -63,7 +605,7 @@ mod tests {
 
     use super::*;
 
-    const BEE_MOVIE_FILE_NAME: &'static str = "src/test/resources/bee_movie.txt";
+    const BEE_MOVIE_FILE_NAME: &str = "src/test/resources/bee_movie.txt";
 
     #[test]
     fn test_opening_bee_movie_script() {
@@ -97,7 +639,7 @@ mod tests {
     #[test]
     fn test_parsing_bee_regex() {
         let search_text = CString::new("[Bb]ee").unwrap();
-        let file = parse_search_text(search_text.as_ptr());
+        let file = parse_search_text(search_text.as_ptr(), &ParsedOptions::default());
         assert!(
             file.is_ok(),
             "Could not parse search text \"[Bb]ee\" using a C-style pointer"
@@ -108,8 +650,316 @@ mod tests {
     fn test_opening_null_search_text_returns_appropriate_error_code() {
         assert_eq!(
             SearchStatusCode::MissingSearchText,
-            parse_search_text(ptr::null())
+            parse_search_text(ptr::null(), &ParsedOptions::default())
                 .expect_err("Should not have been able to parse a search regex from a null string")
         );
     }
+
+    #[test]
+    fn test_pattern_over_size_limit_returns_appropriate_error_code() {
+        let search_text = CString::new(r"\w{100}").unwrap();
+        let options = ParsedOptions {
+            regex_size_limit: 1000,
+            ..ParsedOptions::default()
+        };
+        assert_eq!(
+            SearchStatusCode::ErrorPatternTooBig,
+            parse_search_text(search_text.as_ptr(), &options)
+                .expect_err("Should not have been able to compile a pattern over the size limit")
+        );
+    }
+
+    #[test]
+    fn test_pattern_over_nest_limit_returns_appropriate_error_code() {
+        let search_text = CString::new("(((bee)))").unwrap();
+        let options = ParsedOptions {
+            nest_limit: 2,
+            ..ParsedOptions::default()
+        };
+        assert_eq!(
+            SearchStatusCode::ErrorPatternTooBig,
+            parse_search_text(search_text.as_ptr(), &options)
+                .expect_err("Should not have been able to parse a pattern nested too deeply")
+        );
+    }
+
+    #[test]
+    fn test_invalid_pattern_under_limits_returns_bad_pattern_error_code() {
+        let search_text = CString::new("(bee").unwrap();
+        let options = ParsedOptions {
+            regex_size_limit: 1000,
+            nest_limit: 2,
+            ..ParsedOptions::default()
+        };
+        assert_eq!(
+            SearchStatusCode::ErrorBadPattern,
+            parse_search_text(search_text.as_ptr(), &options)
+                .expect_err("Should not have been able to parse an unclosed group")
+        );
+    }
+
+    #[test]
+    fn test_disabling_unicode_makes_word_class_ascii_only() {
+        use grep::matcher::Matcher;
+
+        let search_text = CString::new(r"^\w+$").unwrap();
+        let unicode = parse_search_text(search_text.as_ptr(), &ParsedOptions::default()).unwrap();
+        let ascii = parse_search_text(
+            search_text.as_ptr(),
+            &ParsedOptions {
+                disable_unicode: true,
+                ..ParsedOptions::default()
+            },
+        )
+        .unwrap();
+
+        assert!(unicode.is_match("résumé".as_bytes()).unwrap());
+        assert!(!ascii.is_match("résumé".as_bytes()).unwrap());
+    }
+
+    #[test]
+    fn test_parsing_null_options_uses_defaults() {
+        assert_eq!(
+            RegexEngine::Default,
+            parse_options(ptr::null()).unwrap().engine
+        );
+    }
+
+    #[test]
+    fn test_parsing_options_converts_enums_and_flags() {
+        let options = SearchOptions {
+            engine: RegexEngine::Auto as c_int,
+            multi_line: 1,
+            ..SearchOptions::default()
+        };
+        let parsed = parse_options(&options).unwrap();
+        assert_eq!(RegexEngine::Auto, parsed.engine);
+        assert!(parsed.multi_line);
+    }
+
+    #[test]
+    fn test_parsing_options_rejects_unknown_enum_value() {
+        let options = SearchOptions {
+            engine: 3,
+            ..SearchOptions::default()
+        };
+        assert_eq!(
+            SearchStatusCode::InvalidOptions,
+            parse_options(&options).unwrap_err()
+        );
+    }
+
+    #[test]
+    fn test_parsing_options_rejects_flag_other_than_zero_or_one() {
+        let options = SearchOptions {
+            invert_match: 2,
+            ..SearchOptions::default()
+        };
+        assert_eq!(
+            SearchStatusCode::InvalidOptions,
+            parse_options(&options).unwrap_err()
+        );
+    }
+
+    #[test]
+    fn test_default_engine_rejects_look_behind() {
+        let search_text = CString::new("(?<=Barry) Benson").unwrap();
+        let options = ParsedOptions::default();
+        assert_eq!(
+            SearchStatusCode::ErrorBadPattern,
+            parse_pattern(search_text.as_ptr(), &options)
+                .expect_err("The default regex engine should not support look-behind")
+        );
+    }
+
+    #[test]
+    fn test_auto_engine_prefers_default_engine() {
+        let search_text = CString::new("[Bb]ee").unwrap();
+        let options = ParsedOptions {
+            engine: RegexEngine::Auto,
+            ..ParsedOptions::default()
+        };
+        match parse_pattern(search_text.as_ptr(), &options) {
+            Ok(PatternMatcher::RustRegex(_)) => {}
+            _ => panic!("A pattern the default engine accepts should not fall back to PCRE2"),
+        }
+    }
+
+    #[cfg(feature = "pcre2")]
+    #[test]
+    fn test_auto_engine_falls_back_to_pcre2_for_look_behind() {
+        let search_text = CString::new("(?<=Barry) Benson").unwrap();
+        let options = ParsedOptions {
+            engine: RegexEngine::Auto,
+            ..ParsedOptions::default()
+        };
+        match parse_pattern(search_text.as_ptr(), &options) {
+            Ok(PatternMatcher::Pcre2(_)) => {}
+            _ => panic!("Look-behind should fall back to the PCRE2 engine"),
+        }
+    }
+
+    #[cfg(not(feature = "pcre2"))]
+    #[test]
+    fn test_pcre2_engine_is_unavailable_without_feature() {
+        let search_text = CString::new("(?<=Barry) Benson").unwrap();
+        let options = ParsedOptions {
+            engine: RegexEngine::Pcre2,
+            ..ParsedOptions::default()
+        };
+        assert_eq!(
+            SearchStatusCode::ErrorEngineUnavailable,
+            parse_pattern(search_text.as_ptr(), &options)
+                .expect_err("PCRE2 should not be available without the `pcre2` feature")
+        );
+    }
+
+    #[test]
+    fn test_parsing_capture_names() {
+        let search_text = CString::new(r"(?P<first>\w+) (\w+) (?P<last>\w+)").unwrap();
+        let matcher = parse_search_text(search_text.as_ptr(), &ParsedOptions::default()).unwrap();
+        let names = parse_capture_names(search_text.as_ptr(), &matcher);
+        assert_eq!(
+            vec![
+                None,
+                Some(CString::new("first").unwrap()),
+                None,
+                Some(CString::new("last").unwrap())
+            ],
+            names
+        );
+    }
+
+    #[test]
+    fn test_look_behind_is_not_a_capture_name() {
+        let names: Vec<&str> = candidate_capture_names("(?<=bee)(?<movie>.*)").collect();
+        assert_eq!(vec!["movie"], names);
+    }
+
+    #[test]
+    fn test_parsing_literal_dictionary() {
+        let literals = [CString::new("bee").unwrap(), CString::new("honey").unwrap()];
+        let pointers: Vec<*const c_char> = literals.iter().map(|l| l.as_ptr()).collect();
+        let matcher = parse_literals(
+            pointers.as_ptr(),
+            pointers.len() as c_int,
+            &ParsedOptions::default(),
+        )
+        .expect("Could not parse a dictionary of two literals");
+        assert_eq!(2, matcher.len());
+    }
+
+    #[test]
+    fn test_parsing_fuzzy_literal_too_short_for_distance_returns_bad_pattern() {
+        let bee = CString::new("bee").unwrap();
+        assert!(parse_fuzzy(bee.as_ptr(), 2, &ParsedOptions::default()).is_ok());
+        assert_eq!(
+            SearchStatusCode::ErrorBadPattern,
+            parse_fuzzy(bee.as_ptr(), 3, &ParsedOptions::default())
+                .expect_err("Every line would match \"bee\" within 3 edits")
+        );
+        assert_eq!(
+            SearchStatusCode::ErrorBadPattern,
+            parse_fuzzy(bee.as_ptr(), -1, &ParsedOptions::default())
+                .expect_err("A negative distance makes no sense")
+        );
+    }
+
+    #[test]
+    fn test_parsing_fuzzy_literal_with_line_terminator_byte_returns_bad_pattern() {
+        let literal = CString::new("bee\tmovie").unwrap();
+        let options = ParsedOptions {
+            line_terminator: LineTerminatorKind::Byte,
+            line_terminator_byte: b'\t',
+            ..ParsedOptions::default()
+        };
+        assert_eq!(
+            SearchStatusCode::ErrorBadPattern,
+            parse_fuzzy(literal.as_ptr(), 1, &options)
+                .expect_err("A literal containing the line terminator could never match one line")
+        );
+    }
+
+    #[test]
+    fn test_parsing_snippet_matches_any_whitespace() {
+        let snippet = CString::new("  bees.stream()\n\t.count();  ").unwrap();
+        let matcher = parse_snippet(snippet.as_ptr(), &ParsedOptions::default()).unwrap();
+        assert!(matcher
+            .is_match(b"return bees.stream()   .count();")
+            .unwrap());
+        assert!(matcher
+            .is_match(b"bees.stream()\n        .count();")
+            .unwrap());
+        assert!(!matcher.is_match(b"bees.stream().count();").unwrap());
+    }
+
+    #[test]
+    fn test_parsing_blank_snippet_returns_bad_pattern() {
+        let snippet = CString::new(" \t\n ").unwrap();
+        assert_eq!(
+            SearchStatusCode::ErrorBadPattern,
+            parse_snippet(snippet.as_ptr(), &ParsedOptions::default())
+                .expect_err("A blank snippet would match everywhere")
+        );
+    }
+
+    #[test]
+    fn test_parsing_null_literal_dictionary_returns_appropriate_error_code() {
+        assert_eq!(
+            SearchStatusCode::MissingSearchText,
+            parse_literals(ptr::null(), 3, &ParsedOptions::default())
+                .expect_err("Should not have been able to parse a null dictionary")
+        );
+    }
+
+    #[test]
+    fn test_parsing_dictionary_with_null_literal_returns_appropriate_error_code() {
+        let bee = CString::new("bee").unwrap();
+        let pointers = [bee.as_ptr(), ptr::null()];
+        assert_eq!(
+            SearchStatusCode::MissingSearchText,
+            parse_literals(pointers.as_ptr(), 2, &ParsedOptions::default()).expect_err(
+                "Should not have been able to parse a dictionary containing a null literal"
+            )
+        );
+    }
+
+    #[test]
+    fn test_parsing_multiline_literal_returns_appropriate_error_code() {
+        let literal = CString::new("bee\nmovie").unwrap();
+        let pointers = [literal.as_ptr()];
+        assert_eq!(
+            SearchStatusCode::ErrorBadPattern,
+            parse_literals(pointers.as_ptr(), 1, &ParsedOptions::default())
+                .expect_err("Should not have been able to parse a literal containing a newline")
+        );
+    }
+
+    #[test]
+    fn test_parsing_literal_with_line_terminator_byte_returns_bad_pattern() {
+        let literal = CString::new("bee\tmovie").unwrap();
+        let pointers = [literal.as_ptr()];
+        let options = ParsedOptions {
+            line_terminator: LineTerminatorKind::Byte,
+            line_terminator_byte: b'\t',
+            ..ParsedOptions::default()
+        };
+        assert_eq!(
+            SearchStatusCode::ErrorBadPattern,
+            parse_literals(pointers.as_ptr(), 1, &options)
+                .expect_err("A literal containing the line terminator could never match one line")
+        );
+    }
+
+    #[test]
+    fn test_parsing_empty_literal_returns_bad_pattern() {
+        let vanessa = CString::new("Vanessa").unwrap();
+        let empty = CString::new("").unwrap();
+        let pointers = [vanessa.as_ptr(), empty.as_ptr()];
+        assert_eq!(
+            SearchStatusCode::ErrorBadPattern,
+            parse_literals(pointers.as_ptr(), 2, &ParsedOptions::default())
+                .expect_err("An empty literal would match everywhere")
+        );
+    }
 }
@@ -0,0 +1,232 @@
+// Searches with PCRE2, even in files that aren't valid UTF-8.
+//
+// In UTF mode, PCRE2 refuses to search anything that isn't valid UTF-8, and turning off its check
+// makes searching invalid UTF-8 undefined behaviour. So each pattern is compiled twice:
+// once in UTF mode for valid text, and once without it, to search other text byte by byte.
+// Without UTF mode, classes like `\w` and `.` only match ASCII, just like the default engine without Unicode,
+// so that's the only mode used when the options turn Unicode off.
+//
+// PCRE2 checks the whole haystack on every search, and so would picking a matcher for it.
+// In multi-line mode, the searcher searches the rest of the file again after every match,
+// so the whole file is checked once first, each of those searches looks up its part of it,
+// and the UTF mode matcher is compiled without PCRE2's own check.
+use std::cell::RefCell;
+use std::ops::Range;
+use std::str;
+
+use grep::matcher::{Match, Matcher};
+use grep::pcre2::{Error, RegexCaptures, RegexMatcher, RegexMatcherBuilder};
+
+// A whole haystack checked ahead of time, as the addresses of its bytes and the offsets of any that aren't UTF-8
+#[derive(Clone, Debug)]
+struct PreparedHaystack {
+    addresses: Range<usize>,
+    invalid: Vec<Range<usize>>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Pcre2Matcher {
+    // only ever given text that's been checked here, and None when Unicode is off
+    utf: Option<RegexMatcher>,
+    // searches byte by byte, or for a pattern that only compiles in UTF mode, like `\x{100}`,
+    // is the UTF mode matcher with PCRE2's check, so searching text that isn't UTF-8 returns an error
+    other: RegexMatcher,
+    prepared: RefCell<Option<PreparedHaystack>>,
+}
+
+impl Pcre2Matcher {
+    // Compiles the pattern with the builder's settings, both with and without UTF mode unless `unicode` is false
+    pub fn new(
+        builder: &RegexMatcherBuilder,
+        pattern: &str,
+        unicode: bool,
+    ) -> Result<Pcre2Matcher, Error> {
+        let bytes = builder.clone().utf(false).ucp(false).build(pattern);
+        if !unicode {
+            return Ok(Pcre2Matcher {
+                utf: None,
+                other: bytes?,
+                prepared: RefCell::new(None),
+            });
+        }
+        let mut utf = builder.clone();
+        utf.utf(true).ucp(true);
+        let other = match bytes {
+            Ok(bytes) => bytes,
+            Err(_) => utf.build(pattern)?,
+        };
+        // Safety: `matcher_for` only hands this matcher text it has found to be valid UTF-8
+        let utf = Some(unsafe { utf.disable_utf_check() }.build(pattern)?);
+        Ok(Pcre2Matcher {
+            utf,
+            other,
+            prepared: RefCell::new(None),
+        })
+    }
+
+    // Checks the whole haystack, so searching any part of it while `search` runs doesn't check it again
+    pub fn with_prepared<T>(&self, haystack: &[u8], search: impl FnOnce() -> T) -> T {
+        if self.utf.is_none() {
+            return search();
+        }
+        let start = haystack.as_ptr() as usize;
+        *self.prepared.borrow_mut() = Some(PreparedHaystack {
+            addresses: start..start + haystack.len(),
+            invalid: invalid_ranges(haystack),
+        });
+        let searched = search();
+        // the haystack may be gone after this, and something else may take its place at the same address
+        *self.prepared.borrow_mut() = None;
+        searched
+    }
+
+    // The matcher that can search the haystack
+    fn matcher_for(&self, haystack: &[u8]) -> &RegexMatcher {
+        match &self.utf {
+            Some(utf) if self.is_utf8(haystack) => utf,
+            _ => &self.other,
+        }
+    }
+
+    // Whether the haystack is valid UTF-8, from the prepared one if it's part of it
+    fn is_utf8(&self, haystack: &[u8]) -> bool {
+        let start = haystack.as_ptr() as usize;
+        if let Some(prepared) = self.prepared.borrow().as_ref() {
+            let addresses = &prepared.addresses;
+            if addresses.start <= start && start + haystack.len() <= addresses.end {
+                let offsets = start - addresses.start..start - addresses.start + haystack.len();
+                let next = prepared.invalid.partition_point(|r| r.end <= offsets.start);
+                let has_invalid = prepared
+                    .invalid
+                    .get(next)
+                    .is_some_and(|r| r.start < offsets.end);
+                // a part cut from the middle of a character isn't valid either, even if the whole haystack is
+                return !has_invalid && starts_character(haystack) && ends_character(haystack);
+            }
+        }
+        str::from_utf8(haystack).is_ok()
+    }
+}
+
+// The offsets of every sequence of bytes that isn't UTF-8
+fn invalid_ranges(haystack: &[u8]) -> Vec<Range<usize>> {
+    let mut ranges = Vec::new();
+    let mut start = 0;
+    while let Err(e) = str::from_utf8(&haystack[start..]) {
+        let invalid = start + e.valid_up_to();
+        let end = e.error_len().map_or(haystack.len(), |len| invalid + len);
+        ranges.push(invalid..end);
+        start = end;
+    }
+    ranges
+}
+
+fn is_continuation(byte: u8) -> bool {
+    byte & 0xc0 == 0x80
+}
+
+fn starts_character(bytes: &[u8]) -> bool {
+    bytes.first().is_none_or(|&b| !is_continuation(b))
+}
+
+// Whether the last character in the bytes is whole, given that they're part of valid UTF-8
+fn ends_character(bytes: &[u8]) -> bool {
+    let tail = &bytes[bytes.len().saturating_sub(4)..];
+    match tail.iter().rposition(|&b| !is_continuation(b)) {
+        Some(last) => str::from_utf8(&tail[last..]).is_ok(),
+        None => tail.is_empty(),
+    }
+}
+
+impl Matcher for Pcre2Matcher {
+    type Captures = RegexCaptures;
+    type Error = Error;
+
+    fn find_at(&self, haystack: &[u8], at: usize) -> Result<Option<Match>, Error> {
+        self.matcher_for(haystack).find_at(haystack, at)
+    }
+
+    // both matchers have the same groups, so either one's captures have room for them
+    fn new_captures(&self) -> Result<RegexCaptures, Error> {
+        self.other.new_captures()
+    }
+
+    fn capture_count(&self) -> usize {
+        self.other.capture_count()
+    }
+
+    fn capture_index(&self, name: &str) -> Option<usize> {
+        self.other.capture_index(name)
+    }
+
+    fn try_find_iter<F, E>(&self, haystack: &[u8], matched: F) -> Result<Result<(), E>, Error>
+    where
+        F: FnMut(Match) -> Result<bool, E>,
+    {
+        self.matcher_for(haystack).try_find_iter(haystack, matched)
+    }
+
+    fn captures_at(
+        &self,
+        haystack: &[u8],
+        at: usize,
+        caps: &mut RegexCaptures,
+    ) -> Result<bool, Error> {
+        self.matcher_for(haystack).captures_at(haystack, at, caps)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matcher(pattern: &str) -> Pcre2Matcher {
+        Pcre2Matcher::new(&RegexMatcherBuilder::new(), pattern, true).unwrap()
+    }
+
+    #[test]
+    fn test_valid_utf8_is_searched_in_utf_mode() {
+        let found = matcher(r"\w+").find("日本".as_bytes()).unwrap();
+        assert_eq!(Some(Match::new(0, 6)), found);
+    }
+
+    #[test]
+    fn test_invalid_utf8_is_searched_byte_by_byte() {
+        let found = matcher(r"(?<=\xff\xfe)Holmes")
+            .find(b"Sherlock \xff\xfeHolmes")
+            .unwrap();
+        assert_eq!(Some(Match::new(11, 17)), found);
+    }
+
+    #[test]
+    fn test_utf_only_pattern_still_searches_valid_utf8() {
+        let matcher = matcher(r"\x{65e5}");
+        assert!(matcher.is_match("日本".as_bytes()).unwrap());
+        assert!(matcher.is_match(b"\xff\xe6\x97\xa5").is_err());
+    }
+
+    #[test]
+    fn test_without_unicode_only_ascii_is_matched_by_classes() {
+        let matcher = Pcre2Matcher::new(&RegexMatcherBuilder::new(), r"^\w.$", false).unwrap();
+        assert!(matcher.is_match(b"ab").unwrap());
+        assert!(!matcher.is_match("日本".as_bytes()).unwrap());
+        assert!(!matcher.is_match("a\u{e9}".as_bytes()).unwrap());
+    }
+
+    #[test]
+    fn test_prepared_haystack_is_checked_by_part() {
+        let matcher = matcher("Holmes");
+        let haystack = [&b"Sherlock\n\xff\xfeHolmes\n"[..], "日本\n".as_bytes()].concat();
+        matcher.with_prepared(&haystack, || {
+            assert!(!matcher.is_utf8(&haystack));
+            assert!(matcher.is_utf8(&haystack[..9]));
+            assert!(!matcher.is_utf8(&haystack[9..]));
+            assert!(matcher.is_utf8(&haystack[11..]));
+            assert!(!matcher.is_utf8(&haystack[10..]));
+            assert!(!matcher.is_utf8(&haystack[18..19]));
+            assert!(!matcher.is_utf8(&haystack[19..]));
+            assert!(matcher.is_utf8(&haystack[18..21]));
+        });
+        assert!(matcher.prepared.borrow().is_none());
+    }
+}
@@ -0,0 +1,241 @@
+// Finds the scope a match is in, like the method or class around it, the way `git grep --show-function` does.
+//
+// A scope starts at a header line, and the scope of a match is the nearest header line before it.
+// The matches in a file come in order, so the file is only read through once, however many matches it has.
+use grep::matcher::Matcher;
+use grep::regex::RegexMatcher;
+
+use crate::types::ScopeLanguage;
+
+// Recognizes the lines which start a scope
+pub struct ScopeHeaders {
+    header: RegexMatcher,
+    // lines which look like headers but aren't, like `if (ready) {` looking like a Java method
+    not_header: Option<RegexMatcher>,
+}
+
+impl ScopeHeaders {
+    pub fn new(header: RegexMatcher) -> Self {
+        ScopeHeaders {
+            header,
+            not_header: None,
+        }
+    }
+
+    // The built-in headers for a language, much like git's own `diff=<language>` patterns
+    pub fn for_language(language: ScopeLanguage) -> Option<Self> {
+        let (header, not_header) = match language {
+            ScopeLanguage::None => return None,
+            ScopeLanguage::Java => (
+                concat!(
+                    r"^[ \t]*(?:[a-z-]+[ \t]+)*(?:class|enum|interface|record)[ \t]+\w",
+                    r"|^[ \t]*(?:[A-Za-z_<>&][\]\[?&<>.,\w]*[ \t]+)+[A-Za-z_]\w*[ \t]*\([^;]*$",
+                ),
+                Some(r"^[ \t]*(?:catch|do|else|for|if|instanceof|new|return|switch|throw|while)\b"),
+            ),
+            ScopeLanguage::Rust => (
+                concat!(
+                    r"^[ \t]*(?:pub(?:\([^)]*\))?[ \t]+)?",
+                    r#"(?:(?:default|const|async|unsafe|extern[ \t]+"[^"]*")[ \t]+)*"#,
+                    r"(?:fn|struct|enum|union|trait|impl|mod|macro_rules!)\b",
+                ),
+                None,
+            ),
+            ScopeLanguage::Python => (r"^[ \t]*(?:async[ \t]+)?(?:def|class)[ \t]+\w", None),
+            ScopeLanguage::C => (
+                concat!(
+                    r"^[A-Za-z_][\w \t*&,]*\([^;]*$",
+                    r"|^(?:typedef[ \t]+)?(?:struct|union|enum)\b[^;]*$",
+                ),
+                None,
+            ),
+        };
+        // the built-in patterns are known to be valid
+        Some(ScopeHeaders {
+            header: RegexMatcher::new(header).ok()?,
+            not_header: not_header.and_then(|pattern| RegexMatcher::new(pattern).ok()),
+        })
+    }
+
+    // Checks one line, without its line terminator
+    pub fn is_header(&self, line: &[u8]) -> bool {
+        let matches = |matcher: &RegexMatcher| matcher.is_match(line).unwrap_or(false);
+        matches(&self.header) && !self.not_header.as_ref().is_some_and(matches)
+    }
+}
+
+// The header line of a scope, without its line terminator
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct ScopeHeader<'h> {
+    pub line_number: u64,
+    pub bytes: &'h [u8],
+}
+
+// Reads forwards through a whole file, remembering the last header it's passed
+pub struct ScopeTracker<'h> {
+    haystack: &'h [u8],
+    terminator: u8,
+    // how far through the file has been read, and the number of the line starting there
+    read_to: usize,
+    line_number: u64,
+    header: Option<ScopeHeader<'h>>,
+}
+
+impl<'h> ScopeTracker<'h> {
+    pub fn new(haystack: &'h [u8], terminator: u8) -> Self {
+        ScopeTracker {
+            haystack,
+            terminator,
+            read_to: 0,
+            line_number: 1,
+            header: None,
+        }
+    }
+
+    // The nearest header on a line before the one holding `offset`.
+    // Offsets have to be asked about in order, since the lines before the last one aren't read again.
+    pub fn header_before(
+        &mut self,
+        headers: &ScopeHeaders,
+        offset: usize,
+    ) -> Option<ScopeHeader<'h>> {
+        while self.read_to < offset {
+            let line_end = self.haystack[self.read_to..]
+                .iter()
+                .position(|&b| b == self.terminator)
+                .map(|i| self.read_to + i + 1)
+                .unwrap_or(self.haystack.len());
+            // the offset is on this line, so it's not before it
+            if line_end > offset {
+                break;
+            }
+
+            let line = self.without_terminator(&self.haystack[self.read_to..line_end]);
+            if headers.is_header(line) {
+                self.header = Some(ScopeHeader {
+                    line_number: self.line_number,
+                    bytes: line,
+                });
+            }
+            self.read_to = line_end;
+            self.line_number += 1;
+        }
+        self.header
+    }
+
+    fn without_terminator(&self, line: &'h [u8]) -> &'h [u8] {
+        let line = line.strip_suffix(&[self.terminator]).unwrap_or(line);
+        // the terminator of a CRLF file is just its line feed
+        line.strip_suffix(b"\r").unwrap_or(line)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const JAVA: &str = "public class Hive {\n    public long countWorkers(List<Bee> bees) {\n        if (bees.isEmpty()) {\n            return 0;\n        }\n        return bees.size();\n    }\n}\n";
+
+    fn headers_of(language: ScopeLanguage, lines: &[&str]) -> Vec<bool> {
+        let headers = ScopeHeaders::for_language(language).expect("Every language has headers");
+        lines
+            .iter()
+            .map(|line| headers.is_header(line.as_bytes()))
+            .collect()
+    }
+
+    #[test]
+    fn test_java_headers() {
+        let lines = [
+            "public final class Hive {",
+            "    private static <T> List<T> swarm(int size) {",
+            "    if (bees.isEmpty()) {",
+            "    } else if (ready) {",
+            "        return bees.stream()",
+            "        bee.buzz();",
+        ];
+        assert_eq!(
+            vec![true, true, false, false, false, false],
+            headers_of(ScopeLanguage::Java, &lines)
+        );
+    }
+
+    #[test]
+    fn test_rust_headers() {
+        let lines = [
+            "pub(crate) async fn fly(&self) {",
+            "impl<T> Hive for T {",
+            "    unsafe extern \"C\" fn buzz() {}",
+            "    let fn_name = 1;",
+        ];
+        assert_eq!(
+            vec![true, true, true, false],
+            headers_of(ScopeLanguage::Rust, &lines)
+        );
+    }
+
+    #[test]
+    fn test_python_headers() {
+        let lines = ["class Hive:", "    async def fly(self):", "    define = 1"];
+        assert_eq!(
+            vec![true, true, false],
+            headers_of(ScopeLanguage::Python, &lines)
+        );
+    }
+
+    #[test]
+    fn test_c_headers() {
+        let lines = [
+            "static int count_workers(struct bee *bees)",
+            "typedef struct hive {",
+            "    buzz(bee);",
+            "int swarm_size(void);",
+        ];
+        assert_eq!(
+            vec![true, true, false, false],
+            headers_of(ScopeLanguage::C, &lines)
+        );
+    }
+
+    #[test]
+    fn test_no_language_has_no_headers() {
+        assert!(ScopeHeaders::for_language(ScopeLanguage::None).is_none());
+    }
+
+    #[test]
+    fn test_tracker_finds_nearest_header_before_each_offset() {
+        let headers = ScopeHeaders::for_language(ScopeLanguage::Java).unwrap();
+        let mut tracker = ScopeTracker::new(JAVA.as_bytes(), b'\n');
+        let offset_of = |text: &str| JAVA.find(text).unwrap();
+
+        assert_eq!(
+            None,
+            tracker.header_before(&headers, offset_of("public class"))
+        );
+        assert_eq!(
+            Some(ScopeHeader {
+                line_number: 1,
+                bytes: b"public class Hive {",
+            }),
+            tracker.header_before(&headers, offset_of("public long"))
+        );
+        assert_eq!(
+            Some(ScopeHeader {
+                line_number: 2,
+                bytes: b"    public long countWorkers(List<Bee> bees) {",
+            }),
+            tracker.header_before(&headers, offset_of("return 0"))
+        );
+    }
+
+    #[test]
+    fn test_tracker_strips_crlf() {
+        let headers = ScopeHeaders::for_language(ScopeLanguage::Python).unwrap();
+        let text = "def fly():\r\n    buzz()\r\n";
+        let mut tracker = ScopeTracker::new(text.as_bytes(), b'\n');
+
+        let header = tracker.header_before(&headers, text.find("buzz").unwrap());
+
+        assert_eq!(Some(&b"def fly():"[..]), header.map(|h| h.bytes));
+    }
+}
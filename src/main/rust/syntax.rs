@@ -0,0 +1,246 @@
+// Translates patterns written in other dialects into the regex syntax the matchers understand.
+//
+// Wildcards and SQL `LIKE` patterns describe a whole value, so they're anchored to match a whole line.
+// POSIX regexes keep their meaning, including their bracket expressions, where a backslash is literal.
+use std::iter::Peekable;
+use std::str::Chars;
+
+use crate::types::PatternSyntax;
+
+pub fn translate(pattern: &str, syntax: PatternSyntax) -> String {
+    match syntax {
+        PatternSyntax::Regex => pattern.to_string(),
+        PatternSyntax::Wildcard => translate_wildcards(pattern, '*', '?'),
+        PatternSyntax::SqlLike => translate_wildcards(pattern, '%', '_'),
+        PatternSyntax::PosixBasic => translate_posix(pattern, true),
+        PatternSyntax::PosixExtended => translate_posix(pattern, false),
+    }
+}
+
+// Everything but the two wildcards is literal, and a backslash makes a wildcard literal too
+fn translate_wildcards(pattern: &str, any_run: char, any_one: char) -> String {
+    let mut regex = String::from("^(?:");
+    let mut chars = pattern.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => push_literal(&mut regex, chars.next().unwrap_or('\\')),
+            c if c == any_run => regex.push_str(".*"),
+            c if c == any_one => regex.push('.'),
+            c => push_literal(&mut regex, c),
+        }
+    }
+    regex.push_str(")$");
+    regex
+}
+
+// Basic regexes use `\(`, `\{` and `\|` for groups, intervals and alternation, where the unescaped characters are literal.
+// Extended regexes are written much like ours.
+fn translate_posix(pattern: &str, basic: bool) -> String {
+    let mut regex = String::with_capacity(pattern.len());
+    let mut chars = pattern.chars().peekable();
+    // at the start of an expression, a basic regex treats `*` as literal and `^` as an anchor
+    let mut at_start = true;
+    while let Some(c) = chars.next() {
+        let starts_expression = at_start;
+        at_start = false;
+        match c {
+            '[' => translate_bracket(&mut chars, &mut regex),
+            '\\' => match chars.next() {
+                Some(escaped) => at_start = translate_escape(escaped, basic, &mut regex),
+                None => regex.push_str(r"\\"),
+            },
+            '^' if basic && !starts_expression => regex.push_str(r"\^"),
+            '^' => {
+                regex.push('^');
+                at_start = true;
+            }
+            '$' if basic && !ends_expression(&chars) => regex.push_str(r"\$"),
+            '*' if basic && starts_expression => regex.push_str(r"\*"),
+            '(' | ')' | '{' | '}' | '|' | '+' | '?' if basic => push_literal(&mut regex, c),
+            '(' | '|' => {
+                regex.push(c);
+                at_start = true;
+            }
+            c => regex.push(c),
+        }
+    }
+    regex
+}
+
+// Translates the character after a backslash outside a bracket expression,
+// returning whether it starts a new expression
+fn translate_escape(escaped: char, basic: bool, regex: &mut String) -> bool {
+    match escaped {
+        '(' | '|' if basic => {
+            regex.push(escaped);
+            return true;
+        }
+        ')' | '{' | '}' | '+' | '?' if basic => regex.push(escaped),
+        // GNU word boundaries
+        '<' | '>' => regex.push_str(r"\b"),
+        // GNU classes and backreferences mean the same thing to us
+        'w' | 'W' | 's' | 'S' | 'b' | 'B' | '1'..='9' => {
+            regex.push('\\');
+            regex.push(escaped);
+        }
+        _ => push_literal(regex, escaped),
+    }
+    false
+}
+
+// In a basic regex, `$` is only an anchor at the end of an expression
+fn ends_expression(rest: &Peekable<Chars>) -> bool {
+    let next: String = rest.clone().take(2).collect();
+    next.is_empty() || next == r"\)" || next == r"\|"
+}
+
+// Copies a bracket expression, whose opening `[` has already been read.
+// Named classes like `[:alpha:]` mean the same to us, but backslashes and our set operators have to be escaped.
+// That includes `--`, so every `-` that isn't making a range is escaped too.
+fn translate_bracket(chars: &mut Peekable<Chars>, regex: &mut String) {
+    regex.push('[');
+    if chars.peek() == Some(&'^') {
+        chars.next();
+        regex.push('^');
+    }
+    // whether the last thing copied was a character, which a `-` could make the start of a range
+    let mut after_character = false;
+    // a `]` right at the start is part of the set, rather than the end of it
+    if chars.peek() == Some(&']') {
+        chars.next();
+        regex.push_str(r"\]");
+        after_character = true;
+    }
+    while let Some(c) = chars.next() {
+        match c {
+            ']' => {
+                regex.push(']');
+                return;
+            }
+            '[' if matches!(chars.peek(), Some(':') | Some('=') | Some('.')) => {
+                translate_bracket_class(chars, regex);
+                after_character = false;
+            }
+            // a range like `a-z`, whose end may itself be a `-`
+            '-' if after_character && !matches!(chars.peek(), Some(']') | None) => {
+                regex.push('-');
+                if let Some(end) = chars.next() {
+                    push_bracket_character(regex, end);
+                }
+                after_character = false;
+            }
+            c => {
+                push_bracket_character(regex, c);
+                after_character = true;
+            }
+        }
+    }
+    // an unclosed bracket is left for the regex engine to report
+}
+
+fn push_bracket_character(regex: &mut String, c: char) {
+    if matches!(c, '\\' | '[' | '&' | '~' | '-') {
+        regex.push('\\');
+    }
+    regex.push(c);
+}
+
+// Copies a `[:class:]`, or the character in an equivalence class `[=c=]` or collating symbol `[.c.]`,
+// whose opening `[` has already been read
+fn translate_bracket_class(chars: &mut Peekable<Chars>, regex: &mut String) {
+    let kind = chars.next().unwrap_or(':');
+    let rest: String = chars.clone().collect();
+    let name = match rest.find(&format!("{}]", kind)) {
+        Some(end) => &rest[..end],
+        None => {
+            // not really a class, so the `[` and what follows are just part of the set
+            regex.push_str(r"\[");
+            regex.push(kind);
+            return;
+        }
+    };
+    if kind == ':' {
+        regex.push_str("[:");
+        regex.push_str(name);
+        regex.push_str(":]");
+    } else {
+        for c in name.chars() {
+            push_literal(regex, c);
+        }
+    }
+    chars.nth(name.chars().count() + 1);
+}
+
+fn push_literal(regex: &mut String, c: char) {
+    let mut buffer = [0; 4];
+    regex.push_str(&regex_syntax::escape(c.encode_utf8(&mut buffer)));
+}
+
+#[cfg(test)]
+mod tests {
+    use grep::matcher::Matcher;
+    use grep::regex::RegexMatcher;
+
+    use super::*;
+
+    #[test]
+    fn test_wildcards_match_whole_line() {
+        assert_eq!(
+            r"^(?:.*\.log)$",
+            translate("*.log", PatternSyntax::Wildcard)
+        );
+        assert_eq!(r"^(?:b.e\*)$", translate(r"b?e\*", PatternSyntax::Wildcard));
+    }
+
+    #[test]
+    fn test_sql_like() {
+        assert_eq!(
+            r"^(?:100% .*bee.)$",
+            translate(r"100\% %bee_", PatternSyntax::SqlLike)
+        );
+    }
+
+    #[test]
+    fn test_basic_regex_groups_and_literals() {
+        assert_eq!(
+            r"(ab)*\+c{2}",
+            translate(r"\(ab\)*+c\{2\}", PatternSyntax::PosixBasic)
+        );
+        assert_eq!(r"\*a\^b$", translate("*a^b$", PatternSyntax::PosixBasic));
+        assert_eq!(r"^\*a\$b", translate("^*a$b", PatternSyntax::PosixBasic));
+    }
+
+    #[test]
+    fn test_extended_regex_is_mostly_unchanged() {
+        assert_eq!(
+            r"^(ab|cd)+\.txt\b",
+            translate(r"^(ab|cd)+\.txt\>", PatternSyntax::PosixExtended)
+        );
+    }
+
+    #[test]
+    fn test_bracket_expressions() {
+        assert_eq!(
+            r"[[:alpha:]\\]",
+            translate(r"[[:alpha:]\]", PatternSyntax::PosixExtended)
+        );
+        assert_eq!(r"[^\]a\&]", translate("[^]a&]", PatternSyntax::PosixBasic));
+        assert_eq!("[e]", translate("[[=e=]]", PatternSyntax::PosixBasic));
+    }
+
+    #[test]
+    fn test_bracket_dashes_do_not_make_set_difference() {
+        assert_eq!(r"[+-\-]", translate("[+--]", PatternSyntax::PosixBasic));
+        assert_eq!(r"[\--/]", translate("[--/]", PatternSyntax::PosixBasic));
+        assert_eq!(
+            r"[\-a-c\-]",
+            translate("[-a-c-]", PatternSyntax::PosixBasic)
+        );
+
+        let matcher = RegexMatcher::new(&translate("^[+--]$", PatternSyntax::PosixBasic)).unwrap();
+        for c in ["+", ",", "-"] {
+            assert!(matcher.is_match(c.as_bytes()).unwrap(), "{} is in +--", c);
+        }
+        assert!(!matcher.is_match(b".").unwrap());
+    }
+}
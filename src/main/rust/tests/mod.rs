@@ -1,4 +1,89 @@
+use std::ffi::CString;
+use std::os::raw::c_int;
+
 use super::*;
 
+mod pattern_validate;
+mod search_blocks;
+mod search_captures;
+mod search_columns;
+mod search_context;
+mod search_count;
 mod search_dir;
+mod search_engine;
+mod search_exists;
 mod search_file;
+mod search_files;
+mod search_fuzzy;
+mod search_invert;
+mod search_line_terminator;
+mod search_literals;
+mod search_match_spans;
+mod search_max_count;
+mod search_max_results;
+mod search_multiline;
+mod search_normalization;
+mod search_only_matching;
+mod search_preview;
+mod search_scope;
+mod search_snippet;
+mod search_syntax;
+mod search_trim;
+
+// Helpers shared by the tests of every search feature
+
+const BEE_MOVIE_FILE_NAME: &str = "src/test/resources/bee_movie.txt";
+const HIVE_JAVA_FILE_NAME: &str = "src/test/resources/hive.java.txt";
+const CRLF_FILE_NAME: &str = "src/test/resources/crlf_lines.txt";
+const SHERLOCK_DIR_PATH: &str = "src/test/resources/data";
+const RESOURCES_DIR_PATH: &str = "src/test/resources";
+
+fn as_cstring(text: &str) -> CString {
+    let error = format!("Could not represent {:?} as a CString", text);
+    CString::new(text).expect(&error)
+}
+
+fn search(
+    filename: &str,
+    pattern: &str,
+    options: &SearchOptions,
+    callback: SearchResultCallbackFn,
+) -> SearchStatusCode {
+    let filename = as_cstring(filename);
+    let search_pattern = as_cstring(pattern);
+    search_path_with_options(
+        filename.as_ptr(),
+        search_pattern.as_ptr(),
+        options,
+        Some(callback),
+    )
+}
+
+fn search_bee_movie(
+    pattern: &str,
+    options: &SearchOptions,
+    callback: SearchResultCallbackFn,
+) -> SearchStatusCode {
+    search(BEE_MOVIE_FILE_NAME, pattern, options, callback)
+}
+
+fn bytes_of(result: &SearchResult) -> Vec<u8> {
+    unsafe { std::slice::from_raw_parts(result.bytes, result.num_bytes as usize) }.to_vec()
+}
+
+fn text_of(result: &SearchResult) -> String {
+    String::from_utf8_lossy(&bytes_of(result)).into_owned()
+}
+
+// (start, end) of a match within its result
+type Span = (c_int, c_int);
+
+fn spans_of(result: &SearchResult) -> Vec<Span> {
+    if result.match_spans.is_null() {
+        return Vec::new();
+    }
+    unsafe { std::slice::from_raw_parts(result.match_spans, result.num_match_spans as usize) }
+        .iter()
+        .map(|span| (span.start, span.end))
+        .collect()
+}
@@ -0,0 +1,165 @@
+use std::ffi::*;
+use std::os::raw::{c_char, c_int};
+use std::ptr;
+
+use super::*;
+
+fn diagnostic_with_buffer(buffer: &mut [c_char]) -> PatternDiagnostic {
+    PatternDiagnostic {
+        kind: PatternErrorKind::None,
+        start_byte: 0,
+        end_byte: 0,
+        start_char: 0,
+        end_char: 0,
+        message: buffer.as_mut_ptr(),
+        message_capacity: buffer.len() as c_int,
+        message_length: 0,
+    }
+}
+
+fn message_of(diagnostic: &PatternDiagnostic) -> String {
+    unsafe { CStr::from_ptr(diagnostic.message) }
+        .to_string_lossy()
+        .into_owned()
+}
+
+fn validate(
+    pattern: &str,
+    options: &SearchOptions,
+    buffer: &mut [c_char],
+) -> (SearchStatusCode, PatternDiagnostic) {
+    let search_text = as_cstring(pattern);
+    let mut diagnostic = diagnostic_with_buffer(buffer);
+    let result_code = pattern_validate(search_text.as_ptr(), options, &mut diagnostic);
+    (result_code, diagnostic)
+}
+
+#[test]
+fn test_validating_good_pattern_reports_no_problem() {
+    let mut buffer = [1 as c_char; 64];
+    let (result_code, diagnostic) = validate("[Bb]ee", &SearchOptions::default(), &mut buffer);
+
+    assert_eq!(SearchStatusCode::Success, result_code);
+    assert_eq!(PatternErrorKind::None, diagnostic.kind);
+    assert_eq!((-1, -1), (diagnostic.start_byte, diagnostic.end_byte));
+    assert_eq!(0, diagnostic.message_length);
+    assert_eq!("", message_of(&diagnostic));
+}
+
+#[test]
+fn test_validating_unclosed_group_reports_its_position() {
+    let mut buffer = [0 as c_char; 64];
+    let (result_code, diagnostic) = validate("bee (movie", &SearchOptions::default(), &mut buffer);
+
+    assert_eq!(SearchStatusCode::ErrorBadPattern, result_code);
+    assert_eq!(PatternErrorKind::Syntax, diagnostic.kind);
+    assert_eq!((4, 5), (diagnostic.start_byte, diagnostic.end_byte));
+    assert_eq!("unclosed group", message_of(&diagnostic));
+}
+
+#[test]
+fn test_validating_reports_span_in_characters_as_well_as_bytes() {
+    let mut buffer = [0 as c_char; 64];
+    // each "é" is two bytes in UTF-8, but one character
+    let (result_code, diagnostic) = validate("résumé(", &SearchOptions::default(), &mut buffer);
+
+    assert_eq!(SearchStatusCode::ErrorBadPattern, result_code);
+    assert_eq!((8, 9), (diagnostic.start_byte, diagnostic.end_byte));
+    assert_eq!((6, 7), (diagnostic.start_char, diagnostic.end_char));
+}
+
+#[test]
+fn test_validating_reports_span_in_utf16_code_units() {
+    let mut buffer = [0 as c_char; 64];
+    // each emoji is four bytes in UTF-8, and a surrogate pair of two code units in UTF-16
+    let (result_code, diagnostic) = validate(
+        "\u{1f600}\u{1f600}(bee",
+        &SearchOptions::default(),
+        &mut buffer,
+    );
+
+    assert_eq!(SearchStatusCode::ErrorBadPattern, result_code);
+    assert_eq!((8, 9), (diagnostic.start_byte, diagnostic.end_byte));
+    assert_eq!((4, 5), (diagnostic.start_char, diagnostic.end_char));
+}
+
+#[test]
+fn test_validating_normalized_pattern_does_not_report_span() {
+    let mut buffer = [0 as c_char; 64];
+    let options = SearchOptions {
+        normalization: NormalizationForm::Nfc as c_int,
+        ..SearchOptions::default()
+    };
+    // NFC composes each "e" and combining accent into one "é", so the unclosed group moves
+    let (result_code, diagnostic) = validate("e\u{301}e\u{301}(b", &options, &mut buffer);
+
+    assert_eq!(SearchStatusCode::ErrorBadPattern, result_code);
+    assert_eq!(PatternErrorKind::Syntax, diagnostic.kind);
+    assert_eq!((-1, -1), (diagnostic.start_byte, diagnostic.end_byte));
+    assert_eq!((-1, -1), (diagnostic.start_char, diagnostic.end_char));
+}
+
+#[test]
+fn test_validating_translated_pattern_does_not_report_span() {
+    let mut buffer = [0 as c_char; 64];
+    let options = SearchOptions {
+        syntax: PatternSyntax::PosixBasic as c_int,
+        ..SearchOptions::default()
+    };
+    // the basic regex `a\(b` becomes `a(b`, so the unclosed group isn't where it was typed
+    let (result_code, diagnostic) = validate(r"a\(b", &options, &mut buffer);
+
+    assert_eq!(SearchStatusCode::ErrorBadPattern, result_code);
+    assert_eq!(PatternErrorKind::Syntax, diagnostic.kind);
+    assert_eq!((-1, -1), (diagnostic.start_byte, diagnostic.end_byte));
+    assert_eq!("unclosed group", message_of(&diagnostic));
+}
+
+#[test]
+fn test_validating_look_behind_reports_unsupported_feature() {
+    let mut buffer = [0 as c_char; 64];
+    let (result_code, diagnostic) =
+        validate("(?<=Barry) Benson", &SearchOptions::default(), &mut buffer);
+
+    assert_eq!(SearchStatusCode::ErrorBadPattern, result_code);
+    assert_eq!(PatternErrorKind::Unsupported, diagnostic.kind);
+    assert_eq!(0, diagnostic.start_byte);
+}
+
+#[test]
+fn test_validating_pattern_over_nest_limit_reports_too_big() {
+    let mut buffer = [0 as c_char; 64];
+    let options = SearchOptions {
+        nest_limit: 2,
+        ..SearchOptions::default()
+    };
+    let (result_code, diagnostic) = validate("(((bee)))", &options, &mut buffer);
+
+    assert_eq!(SearchStatusCode::ErrorPatternTooBig, result_code);
+    assert_eq!(PatternErrorKind::TooBig, diagnostic.kind);
+}
+
+#[test]
+fn test_validating_truncates_message_to_fit_buffer() {
+    let mut buffer = [0 as c_char; 5];
+    let (_, diagnostic) = validate("bee (movie", &SearchOptions::default(), &mut buffer);
+
+    assert_eq!("unclosed group".len() as c_int, diagnostic.message_length);
+    assert_eq!("uncl", message_of(&diagnostic));
+}
+
+#[test]
+fn test_validating_without_diagnostic_only_returns_status_code() {
+    let search_text = as_cstring("bee (movie");
+
+    let result_code = pattern_validate(search_text.as_ptr(), ptr::null(), ptr::null_mut());
+
+    assert_eq!(SearchStatusCode::ErrorBadPattern, result_code);
+}
+
+#[test]
+fn test_validating_null_search_text_returns_missing_search_text_error_code() {
+    let result_code = pattern_validate(ptr::null(), ptr::null(), ptr::null_mut());
+
+    assert_eq!(SearchStatusCode::MissingSearchText, result_code);
+}
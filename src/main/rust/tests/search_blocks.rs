@@ -0,0 +1,163 @@
+use std::os::raw::c_int;
+use std::sync::Mutex;
+
+use super::*;
+
+const HIVE_LOG_FILE_NAME: &str = "src/test/resources/hive.log";
+
+#[test]
+fn test_match_is_reported_with_its_whole_record() {
+    let record_start = as_cstring(r"^\d{4}-\d{2}-\d{2}");
+    let options = SearchOptions {
+        block_context: BlockContext::Record as c_int,
+        record_start: record_start.as_ptr(),
+        ..SearchOptions::default()
+    };
+
+    let result_code = search(
+        HIVE_LOG_FILE_NAME,
+        "Hive.java:42",
+        &options,
+        record_record_callback,
+    );
+
+    assert_eq!(SearchStatusCode::Success, result_code);
+    assert_eq!(
+        vec![(
+            2,
+            4,
+            "2007-11-02 09:15:41 ERROR Pollen jock recruitment failed\n    \
+             at Hive.recruit(Hive.java:42)\n    at Hive.main(Hive.java:7)\n"
+                .to_string()
+        )],
+        *RECORDS.lock().unwrap(),
+        "A match inside a stack trace should be reported with the whole log entry it belongs to"
+    );
+}
+
+#[test]
+fn test_match_is_reported_with_its_whole_paragraph() {
+    let options = SearchOptions {
+        block_context: BlockContext::Paragraph as c_int,
+        ..SearchOptions::default()
+    };
+
+    let result_code = search(
+        HIVE_LOG_FILE_NAME,
+        "honey",
+        &options,
+        record_paragraph_callback,
+    );
+
+    assert_eq!(SearchStatusCode::Success, result_code);
+    assert_eq!(
+        vec![(
+            7,
+            8,
+            "Adam Flayman works at Honex.\nHe stirs the honey.\n".to_string()
+        )],
+        *PARAGRAPHS.lock().unwrap(),
+        "The blank lines around the paragraph should not be part of it"
+    );
+}
+
+#[test]
+fn test_block_with_several_matches_is_reported_once() {
+    let options = SearchOptions {
+        block_context: BlockContext::Paragraph as c_int,
+        ..SearchOptions::default()
+    };
+
+    let result_code = search(
+        HIVE_LOG_FILE_NAME,
+        "Hive",
+        &options,
+        record_several_callback,
+    );
+
+    assert_eq!(SearchStatusCode::Success, result_code);
+    assert_eq!(vec![(1, 5)], *SEVERAL.lock().unwrap());
+}
+
+#[test]
+fn test_inverted_block_search_reports_blocks_without_matches() {
+    let options = SearchOptions {
+        invert_match: 1,
+        block_context: BlockContext::Paragraph as c_int,
+        ..SearchOptions::default()
+    };
+
+    let result_code = search(
+        HIVE_LOG_FILE_NAME,
+        "Honex",
+        &options,
+        record_inverted_callback,
+    );
+
+    assert_eq!(SearchStatusCode::Success, result_code);
+    assert_eq!(
+        vec![(10, 10, SearchResultKind::InvertedMatch)],
+        *INVERTED.lock().unwrap()
+    );
+}
+
+#[test]
+fn test_bad_record_start_returns_bad_pattern_error_code() {
+    let record_start = as_cstring("(");
+    let options = SearchOptions {
+        block_context: BlockContext::Record as c_int,
+        record_start: record_start.as_ptr(),
+        ..SearchOptions::default()
+    };
+
+    let result_code = search(
+        HIVE_LOG_FILE_NAME,
+        "Hive",
+        &options,
+        record_bad_start_callback,
+    );
+
+    assert_eq!(SearchStatusCode::ErrorBadPattern, result_code);
+}
+
+// each test records into its own list, so tests running concurrently don't interfere
+static RECORDS: Mutex<Vec<(c_int, c_int, String)>> = Mutex::new(Vec::new());
+static PARAGRAPHS: Mutex<Vec<(c_int, c_int, String)>> = Mutex::new(Vec::new());
+static SEVERAL: Mutex<Vec<(c_int, c_int)>> = Mutex::new(Vec::new());
+static INVERTED: Mutex<Vec<(c_int, c_int, SearchResultKind)>> = Mutex::new(Vec::new());
+
+extern "C" fn record_record_callback(result: SearchResult) -> bool {
+    RECORDS
+        .lock()
+        .unwrap()
+        .push((result.line_number, result.end_line_number, text_of(&result)));
+    true
+}
+
+extern "C" fn record_paragraph_callback(result: SearchResult) -> bool {
+    PARAGRAPHS
+        .lock()
+        .unwrap()
+        .push((result.line_number, result.end_line_number, text_of(&result)));
+    true
+}
+
+extern "C" fn record_several_callback(result: SearchResult) -> bool {
+    SEVERAL
+        .lock()
+        .unwrap()
+        .push((result.line_number, result.end_line_number));
+    true
+}
+
+extern "C" fn record_inverted_callback(result: SearchResult) -> bool {
+    INVERTED
+        .lock()
+        .unwrap()
+        .push((result.line_number, result.end_line_number, result.kind));
+    true
+}
+
+extern "C" fn record_bad_start_callback(_: SearchResult) -> bool {
+    true
+}
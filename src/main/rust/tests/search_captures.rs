@@ -0,0 +1,182 @@
+use std::ffi::*;
+use std::os::raw::c_int;
+use std::sync::Mutex;
+
+use super::*;
+
+fn with_captures() -> SearchOptions {
+    SearchOptions {
+        report_captures: 1,
+        ..SearchOptions::default()
+    }
+}
+
+// (match index, group index, name, start, end, text) for every group of a result
+type Group = (c_int, c_int, Option<String>, c_int, c_int, Option<String>);
+
+fn groups_of(result: &SearchResult) -> Vec<Group> {
+    if result.captures.is_null() {
+        return Vec::new();
+    }
+    let captures =
+        unsafe { std::slice::from_raw_parts(result.captures, result.num_captures as usize) };
+    captures
+        .iter()
+        .map(|group| {
+            let name = if group.name.is_null() {
+                None
+            } else {
+                Some(
+                    unsafe { CStr::from_ptr(group.name) }
+                        .to_string_lossy()
+                        .into_owned(),
+                )
+            };
+            let text = if group.bytes.is_null() {
+                None
+            } else {
+                let bytes =
+                    unsafe { std::slice::from_raw_parts(group.bytes, group.num_bytes as usize) };
+                Some(String::from_utf8_lossy(bytes).into_owned())
+            };
+            (
+                group.match_index,
+                group.group_index,
+                name,
+                group.start,
+                group.end,
+                text,
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn test_named_groups_are_reported_with_their_names() {
+    let result_code = search_bee_movie(
+        r"Special (?P<what>\w+), (\w+)",
+        &with_captures(),
+        record_named_callback,
+    );
+
+    assert_eq!(SearchStatusCode::Success, result_code);
+    assert_eq!(
+        vec![(
+            13,
+            vec![
+                (
+                    0,
+                    0,
+                    None,
+                    10,
+                    33,
+                    Some("Special day, graduation".to_string())
+                ),
+                (
+                    0,
+                    1,
+                    Some("what".to_string()),
+                    18,
+                    21,
+                    Some("day".to_string())
+                ),
+                (0, 2, None, 23, 33, Some("graduation".to_string())),
+            ]
+        )],
+        *NAMED.lock().unwrap()
+    );
+}
+
+#[test]
+fn test_every_match_on_a_line_is_captured() {
+    let result_code = search_bee_movie(
+        r"Three days (?P<school>\w+)",
+        &with_captures(),
+        record_every_match_callback,
+    );
+
+    assert_eq!(SearchStatusCode::Success, result_code);
+    let results = EVERY_MATCH.lock().unwrap();
+    let line_13 = &results.iter().find(|(line, _)| *line == 13).unwrap().1;
+    let schools: Vec<(c_int, Option<String>)> = line_13
+        .iter()
+        .filter(|group| group.1 == 1)
+        .map(|group| (group.0, group.5.clone()))
+        .collect();
+    assert_eq!(
+        vec![
+            (0, Some("grade".to_string())),
+            (1, Some("college".to_string()))
+        ],
+        schools,
+        "Both matches on line 13 should report their groups, in order"
+    );
+}
+
+#[test]
+fn test_group_that_does_not_participate_has_no_span() {
+    let result_code = search_bee_movie(
+        r"graduation(?P<missing>!)?",
+        &with_captures(),
+        record_missing_callback,
+    );
+
+    assert_eq!(SearchStatusCode::Success, result_code);
+    assert_eq!(
+        vec![(0, 1, Some("missing".to_string()), -1, -1, None)],
+        MISSING
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|group| group.1 == 1)
+            .cloned()
+            .collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn test_captures_are_not_reported_by_default() {
+    let result_code = search_bee_movie(
+        r"Special (?P<what>\w+)",
+        &SearchOptions::default(),
+        record_default_callback,
+    );
+
+    assert_eq!(SearchStatusCode::Success, result_code);
+    assert_eq!(vec![(13, 0)], *DEFAULT.lock().unwrap());
+}
+
+// each test records into its own list, so tests running concurrently don't interfere
+static NAMED: Mutex<Vec<(c_int, Vec<Group>)>> = Mutex::new(Vec::new());
+static EVERY_MATCH: Mutex<Vec<(c_int, Vec<Group>)>> = Mutex::new(Vec::new());
+static MISSING: Mutex<Vec<Group>> = Mutex::new(Vec::new());
+static DEFAULT: Mutex<Vec<(c_int, c_int)>> = Mutex::new(Vec::new());
+
+extern "C" fn record_named_callback(result: SearchResult) -> bool {
+    NAMED
+        .lock()
+        .unwrap()
+        .push((result.line_number, groups_of(&result)));
+    true
+}
+
+extern "C" fn record_every_match_callback(result: SearchResult) -> bool {
+    EVERY_MATCH
+        .lock()
+        .unwrap()
+        .push((result.line_number, groups_of(&result)));
+    true
+}
+
+extern "C" fn record_missing_callback(result: SearchResult) -> bool {
+    MISSING.lock().unwrap().extend(groups_of(&result));
+    true
+}
+
+extern "C" fn record_default_callback(result: SearchResult) -> bool {
+    DEFAULT
+        .lock()
+        .unwrap()
+        .push((result.line_number, result.num_captures));
+    true
+}
@@ -0,0 +1,111 @@
+use std::os::raw::c_int;
+use std::sync::Mutex;
+
+use super::*;
+
+// a tab, then "café 😀 bee"
+const COLUMNS_FILE_NAME: &str = "src/test/resources/columns.txt";
+
+fn counting_in(column_unit: ColumnUnit) -> SearchOptions {
+    SearchOptions {
+        column_unit: column_unit as c_int,
+        ..SearchOptions::default()
+    }
+}
+
+#[test]
+fn test_column_is_reported_in_bytes_by_default() {
+    let result_code = search(
+        BEE_MOVIE_FILE_NAME,
+        "graduation",
+        &SearchOptions::default(),
+        record_default_callback,
+    );
+
+    assert_eq!(SearchStatusCode::Success, result_code);
+    assert_eq!(vec![(13, 24)], *DEFAULT.lock().unwrap());
+}
+
+#[test]
+fn test_column_is_reported_in_each_unit() {
+    let units = [
+        (ColumnUnit::Byte, 13),
+        (ColumnUnit::Char, 9),
+        (ColumnUnit::Utf16, 10),
+        (ColumnUnit::Grapheme, 9),
+    ];
+    for (unit, expected_column) in units.iter() {
+        let result_code = search(
+            COLUMNS_FILE_NAME,
+            "bee",
+            &counting_in(*unit),
+            record_unit_callback,
+        );
+
+        assert_eq!(SearchStatusCode::Success, result_code);
+        assert_eq!(
+            Some(*expected_column),
+            UNITS.lock().unwrap().pop(),
+            "Wrong column when counting in {:?}",
+            unit
+        );
+    }
+}
+
+#[test]
+fn test_tabs_are_expanded_to_tab_width() {
+    let options = SearchOptions {
+        tab_width: 4,
+        ..counting_in(ColumnUnit::Char)
+    };
+
+    let result_code = search(COLUMNS_FILE_NAME, "bee", &options, record_tab_callback);
+
+    assert_eq!(SearchStatusCode::Success, result_code);
+    assert_eq!(vec![12], *TABS.lock().unwrap());
+}
+
+#[test]
+fn test_inverted_results_have_no_column() {
+    let options = SearchOptions {
+        invert_match: 1,
+        ..SearchOptions::default()
+    };
+
+    let result_code = search(COLUMNS_FILE_NAME, "bee", &options, record_inverted_callback);
+
+    assert_eq!(SearchStatusCode::Success, result_code);
+    assert_eq!(vec![(2, -1)], *INVERTED.lock().unwrap());
+}
+
+// each test records into its own list, so tests running concurrently don't interfere
+static DEFAULT: Mutex<Vec<(c_int, c_int)>> = Mutex::new(Vec::new());
+static UNITS: Mutex<Vec<c_int>> = Mutex::new(Vec::new());
+static TABS: Mutex<Vec<c_int>> = Mutex::new(Vec::new());
+static INVERTED: Mutex<Vec<(c_int, c_int)>> = Mutex::new(Vec::new());
+
+extern "C" fn record_default_callback(result: SearchResult) -> bool {
+    DEFAULT
+        .lock()
+        .unwrap()
+        .push((result.line_number, result.column));
+    true
+}
+
+extern "C" fn record_unit_callback(result: SearchResult) -> bool {
+    UNITS.lock().unwrap().push(result.column);
+    true
+}
+
+extern "C" fn record_tab_callback(result: SearchResult) -> bool {
+    TABS.lock().unwrap().push(result.column);
+    true
+}
+
+extern "C" fn record_inverted_callback(result: SearchResult) -> bool {
+    INVERTED
+        .lock()
+        .unwrap()
+        .push((result.line_number, result.column));
+    true
+}
@@ -0,0 +1,100 @@
+use std::os::raw::c_int;
+use std::sync::Mutex;
+
+use super::*;
+
+fn with_context(before_context: u32, after_context: u32) -> SearchOptions {
+    SearchOptions {
+        before_context,
+        after_context,
+        ..SearchOptions::default()
+    }
+}
+
+#[test]
+fn test_context_lines_surround_the_match() {
+    let result_code = search_bee_movie("graduation", &with_context(1, 1), record_context_callback);
+
+    assert_eq!(SearchStatusCode::Success, result_code);
+    let results = CONTEXT.lock().unwrap();
+    assert_eq!(
+        vec![
+            (12, SearchResultKind::BeforeContext),
+            (13, SearchResultKind::Match),
+            (14, SearchResultKind::AfterContext),
+        ],
+        results
+            .iter()
+            .map(|(line, kind, _)| (*line, *kind))
+            .collect::<Vec<_>>()
+    );
+    assert_eq!("Is that fuzz gel?\n", results[0].2);
+}
+
+#[test]
+fn test_context_breaks_separate_hunks() {
+    let result_code = search_bee_movie("Barry Benson", &with_context(0, 1), record_break_callback);
+
+    assert_eq!(SearchStatusCode::Success, result_code);
+    use SearchResultKind::*;
+    assert_eq!(
+        vec![
+            (195, Match),
+            (196, AfterContext),
+            (-1, ContextBreak),
+            (223, Match),
+            (224, AfterContext),
+            (-1, ContextBreak),
+            (279, Match),
+            (280, AfterContext),
+            (281, Match),
+            (282, AfterContext),
+        ],
+        *BREAKS.lock().unwrap(),
+        "Hunks which don't touch should be separated by a break, and hunks which do should be merged"
+    );
+}
+
+#[test]
+fn test_no_context_by_default() {
+    let result_code = search_bee_movie(
+        "graduation",
+        &SearchOptions::default(),
+        record_default_callback,
+    );
+
+    assert_eq!(SearchStatusCode::Success, result_code);
+    assert_eq!(
+        vec![(13, SearchResultKind::Match)],
+        *DEFAULT.lock().unwrap()
+    );
+}
+
+// each test records into its own list, so tests running concurrently don't interfere
+static CONTEXT: Mutex<Vec<(c_int, SearchResultKind, String)>> = Mutex::new(Vec::new());
+static BREAKS: Mutex<Vec<(c_int, SearchResultKind)>> = Mutex::new(Vec::new());
+static DEFAULT: Mutex<Vec<(c_int, SearchResultKind)>> = Mutex::new(Vec::new());
+
+extern "C" fn record_context_callback(result: SearchResult) -> bool {
+    CONTEXT
+        .lock()
+        .unwrap()
+        .push((result.line_number, result.kind, text_of(&result)));
+    true
+}
+
+extern "C" fn record_break_callback(result: SearchResult) -> bool {
+    BREAKS
+        .lock()
+        .unwrap()
+        .push((result.line_number, result.kind));
+    true
+}
+
+extern "C" fn record_default_callback(result: SearchResult) -> bool {
+    DEFAULT
+        .lock()
+        .unwrap()
+        .push((result.line_number, result.kind));
+    true
+}
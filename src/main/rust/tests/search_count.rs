@@ -0,0 +1,178 @@
+use std::fs;
+use std::sync::Mutex;
+
+use super::*;
+
+fn bee_movie_lines() -> Vec<String> {
+    fs::read_to_string(BEE_MOVIE_FILE_NAME)
+        .expect("Could not read the Bee Movie script")
+        .lines()
+        .map(|line| line.to_string())
+        .collect()
+}
+
+#[test]
+fn test_counting_lines_reports_one_result_per_file() {
+    let options = SearchOptions {
+        count: CountMode::Lines as c_int,
+        ..SearchOptions::default()
+    };
+
+    let result_code = search(BEE_MOVIE_FILE_NAME, "bee", &options, record_lines_callback);
+
+    assert_eq!(SearchStatusCode::Success, result_code);
+    let expected = bee_movie_lines()
+        .iter()
+        .filter(|line| line.contains("bee"))
+        .count() as i64;
+    assert_eq!(
+        vec![(SearchResultKind::Count, 0, expected, -1)],
+        *LINE_COUNTS.lock().unwrap(),
+        "Counting lines should report only the number of matching lines, without any bytes"
+    );
+}
+
+#[test]
+fn test_counting_matches_counts_every_match_on_a_line() {
+    let options = SearchOptions {
+        count: CountMode::Matches as c_int,
+        ..SearchOptions::default()
+    };
+
+    let result_code = search(
+        BEE_MOVIE_FILE_NAME,
+        "bee",
+        &options,
+        record_matches_callback,
+    );
+
+    assert_eq!(SearchStatusCode::Success, result_code);
+    let lines = bee_movie_lines();
+    let matching_lines = lines.iter().filter(|line| line.contains("bee")).count() as i64;
+    let matches = lines
+        .iter()
+        .map(|line| line.matches("bee").count())
+        .sum::<usize>() as i64;
+    assert!(matches > matching_lines);
+    assert_eq!(
+        vec![(matching_lines, matches)],
+        *MATCH_COUNTS.lock().unwrap()
+    );
+}
+
+#[test]
+fn test_counting_inverted_search_counts_lines_that_do_not_match() {
+    let options = SearchOptions {
+        count: CountMode::Matches as c_int,
+        invert_match: 1,
+        ..SearchOptions::default()
+    };
+
+    let result_code = search(
+        BEE_MOVIE_FILE_NAME,
+        "graduation",
+        &options,
+        record_inverted_callback,
+    );
+
+    assert_eq!(SearchStatusCode::Success, result_code);
+    let expected = bee_movie_lines()
+        .iter()
+        .filter(|line| !line.contains("graduation"))
+        .count() as i64;
+    assert_eq!(vec![(expected, expected)], *INVERTED_COUNTS.lock().unwrap());
+}
+
+#[test]
+fn test_counting_dir_skips_files_without_matches() {
+    let options = SearchOptions {
+        count: CountMode::Lines as c_int,
+        ..SearchOptions::default()
+    };
+
+    let result_code = search(
+        SHERLOCK_DIR_PATH,
+        "Roger Squires",
+        &options,
+        record_dir_callback,
+    );
+
+    assert_eq!(SearchStatusCode::Success, result_code);
+    assert_eq!(
+        vec![(SearchResultKind::Count, 1)],
+        *DIR_COUNTS.lock().unwrap(),
+        "Only one file mentions Roger Squires, so no other file should be counted"
+    );
+}
+
+#[test]
+fn test_counting_ignores_context() {
+    let options = SearchOptions {
+        count: CountMode::Lines as c_int,
+        before_context: 2,
+        after_context: 2,
+        ..SearchOptions::default()
+    };
+
+    let result_code = search(
+        BEE_MOVIE_FILE_NAME,
+        "graduation",
+        &options,
+        record_context_callback,
+    );
+
+    assert_eq!(SearchStatusCode::Success, result_code);
+    assert_eq!(
+        vec![(SearchResultKind::Count, 1)],
+        *CONTEXT_COUNTS.lock().unwrap()
+    );
+}
+
+// each test records into its own list, so tests running concurrently don't interfere
+static LINE_COUNTS: Mutex<Vec<(SearchResultKind, i32, i64, i64)>> = Mutex::new(Vec::new());
+static MATCH_COUNTS: Mutex<Vec<(i64, i64)>> = Mutex::new(Vec::new());
+static INVERTED_COUNTS: Mutex<Vec<(i64, i64)>> = Mutex::new(Vec::new());
+static DIR_COUNTS: Mutex<Vec<(SearchResultKind, i64)>> = Mutex::new(Vec::new());
+static CONTEXT_COUNTS: Mutex<Vec<(SearchResultKind, i64)>> = Mutex::new(Vec::new());
+
+extern "C" fn record_lines_callback(result: SearchResult) -> bool {
+    LINE_COUNTS.lock().unwrap().push((
+        result.kind,
+        result.num_bytes,
+        result.num_matching_lines,
+        result.num_matches,
+    ));
+    true
+}
+
+extern "C" fn record_matches_callback(result: SearchResult) -> bool {
+    MATCH_COUNTS
+        .lock()
+        .unwrap()
+        .push((result.num_matching_lines, result.num_matches));
+    true
+}
+
+extern "C" fn record_inverted_callback(result: SearchResult) -> bool {
+    INVERTED_COUNTS
+        .lock()
+        .unwrap()
+        .push((result.num_matching_lines, result.num_matches));
+    true
+}
+
+extern "C" fn record_dir_callback(result: SearchResult) -> bool {
+    DIR_COUNTS
+        .lock()
+        .unwrap()
+        .push((result.kind, result.num_matching_lines));
+    true
+}
+
+extern "C" fn record_context_callback(result: SearchResult) -> bool {
+    CONTEXT_COUNTS
+        .lock()
+        .unwrap()
+        .push((result.kind, result.num_matching_lines));
+    true
+}
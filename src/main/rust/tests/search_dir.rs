@@ -1,9 +1,12 @@
+// these tests exercise the deprecated entry points, which must keep working
+#![allow(deprecated)]
+
 use std::ffi::*;
 use std::ptr;
 
 use super::*;
 
-const SHERLOCK_DIR_PATH: &'static str = "src/test/resources/data";
+const SHERLOCK_DIR_PATH: &str = "src/test/resources/data";
 
 fn as_cstring(text: &str) -> CString {
     let error = format!("Could not represent {:?} as a CString", text);
@@ -0,0 +1,333 @@
+#[cfg(feature = "pcre2")]
+use std::env;
+#[cfg(feature = "pcre2")]
+use std::fs;
+#[cfg(feature = "pcre2")]
+use std::os::raw::c_int;
+use std::ptr;
+#[cfg(feature = "pcre2")]
+use std::sync::Mutex;
+#[cfg(feature = "pcre2")]
+use std::time::{Duration, Instant};
+
+use super::*;
+
+// look-behind is supported by PCRE2 (and java.util.regex), but not by the default engine
+const LOOK_BEHIND_PATTERN: &str = "(?<=Special day, )graduation";
+
+// "Sherlock Holmes" twice, the first time followed by bytes which aren't UTF-8
+#[cfg(feature = "pcre2")]
+const NOT_UTF8_FILE_NAME: &str = "src/test/resources/not_utf8.txt";
+
+fn options_with_engine(engine: RegexEngine) -> SearchOptions {
+    SearchOptions {
+        engine: engine as c_int,
+        ..SearchOptions::default()
+    }
+}
+
+#[test]
+fn test_search_with_null_options_uses_default_engine() {
+    let filename = as_cstring(BEE_MOVIE_FILE_NAME);
+    let search_pattern = as_cstring("[Bb]ee");
+
+    let result_code = search_path_with_options(
+        filename.as_ptr(),
+        search_pattern.as_ptr(),
+        ptr::null(),
+        Some(always_succeeding_callback),
+    );
+
+    assert_eq!(
+        SearchStatusCode::Success,
+        result_code,
+        "When passing null options, the extern search_path_with_options function should search with the defaults"
+    );
+}
+
+#[test]
+fn test_search_with_unknown_engine_returns_invalid_options_error_code() {
+    let filename = as_cstring(BEE_MOVIE_FILE_NAME);
+    let search_pattern = as_cstring("[Bb]ee");
+    let options = SearchOptions {
+        engine: 7,
+        ..SearchOptions::default()
+    };
+
+    let result_code = search_path_with_options(
+        filename.as_ptr(),
+        search_pattern.as_ptr(),
+        &options,
+        Some(always_succeeding_callback),
+    );
+
+    assert_eq!(SearchStatusCode::InvalidOptions, result_code);
+}
+
+#[test]
+fn test_search_for_look_behind_with_default_engine_returns_bad_pattern_error_code() {
+    let filename = as_cstring(BEE_MOVIE_FILE_NAME);
+    let search_pattern = as_cstring(LOOK_BEHIND_PATTERN);
+    let options = options_with_engine(RegexEngine::Default);
+
+    let result_code = search_path_with_options(
+        filename.as_ptr(),
+        search_pattern.as_ptr(),
+        &options,
+        Some(always_succeeding_callback),
+    );
+
+    assert_eq!(SearchStatusCode::ErrorBadPattern, result_code);
+}
+
+#[test]
+fn test_search_for_pattern_over_size_limit_returns_pattern_too_big_error_code() {
+    let filename = as_cstring(BEE_MOVIE_FILE_NAME);
+    let search_pattern = as_cstring(r"\w{1000}");
+    let options = SearchOptions {
+        regex_size_limit: 1 << 16,
+        ..SearchOptions::default()
+    };
+
+    let result_code = search_path_with_options(
+        filename.as_ptr(),
+        search_pattern.as_ptr(),
+        &options,
+        Some(always_succeeding_callback),
+    );
+
+    assert_eq!(
+        SearchStatusCode::ErrorPatternTooBig,
+        result_code,
+        "A pattern over the configured size limit should be rejected with {:?}, not {:?}",
+        SearchStatusCode::ErrorPatternTooBig,
+        SearchStatusCode::ErrorBadPattern
+    );
+}
+
+#[cfg(feature = "pcre2")]
+#[test]
+fn test_search_for_look_behind_with_pcre2_engine() {
+    let filename = as_cstring(BEE_MOVIE_FILE_NAME);
+    let search_pattern = as_cstring(LOOK_BEHIND_PATTERN);
+    let options = options_with_engine(RegexEngine::Pcre2);
+
+    let result_code = search_path_with_options(
+        filename.as_ptr(),
+        search_pattern.as_ptr(),
+        &options,
+        Some(record_pcre2_callback),
+    );
+
+    assert_eq!(SearchStatusCode::Success, result_code);
+    assert_eq!(vec![13], *PCRE2_LINES.lock().unwrap());
+}
+
+#[cfg(feature = "pcre2")]
+#[test]
+fn test_search_for_look_behind_with_auto_engine_falls_back_to_pcre2() {
+    let filename = as_cstring(BEE_MOVIE_FILE_NAME);
+    let search_pattern = as_cstring(LOOK_BEHIND_PATTERN);
+    let options = options_with_engine(RegexEngine::Auto);
+
+    let result_code = search_path_with_options(
+        filename.as_ptr(),
+        search_pattern.as_ptr(),
+        &options,
+        Some(record_auto_callback),
+    );
+
+    assert_eq!(SearchStatusCode::Success, result_code);
+    assert_eq!(vec![13], *AUTO_LINES.lock().unwrap());
+}
+
+#[cfg(feature = "pcre2")]
+#[test]
+fn test_pcre2_engine_searches_lines_which_are_not_utf8() {
+    let options = options_with_engine(RegexEngine::Pcre2);
+
+    let result_code = search(
+        NOT_UTF8_FILE_NAME,
+        "(?<=Sherlock )Holmes",
+        &options,
+        record_not_utf8_callback,
+    );
+
+    assert_eq!(SearchStatusCode::Success, result_code);
+    assert_eq!(vec![1, 2], *NOT_UTF8_LINES.lock().unwrap());
+}
+
+#[cfg(feature = "pcre2")]
+#[test]
+fn test_pcre2_engine_searches_lines_which_are_not_utf8_in_multi_line_mode() {
+    let options = SearchOptions {
+        multi_line: 1,
+        ..options_with_engine(RegexEngine::Pcre2)
+    };
+
+    let result_code = search(
+        NOT_UTF8_FILE_NAME,
+        "(?<=Sherlock )Holmes",
+        &options,
+        record_not_utf8_multi_line_callback,
+    );
+
+    assert_eq!(SearchStatusCode::Success, result_code);
+    assert_eq!(vec![1, 2], *NOT_UTF8_MULTI_LINE_LINES.lock().unwrap());
+}
+
+#[cfg(feature = "pcre2")]
+#[test]
+fn test_pcre2_engine_checks_file_for_utf8_once_in_multi_line_mode() {
+    // checking the rest of the file again after every match took seconds for this many lines
+    let path = env::temp_dir().join(format!("ripgrep_ffi_bees_{}.txt", std::process::id()));
+    let text: String = (0..20_000).map(|i| format!("bee number {}\n", i)).collect();
+    fs::write(&path, text).unwrap();
+    let options = SearchOptions {
+        multi_line: 1,
+        ..options_with_engine(RegexEngine::Pcre2)
+    };
+
+    let started = Instant::now();
+    let result_code = search(path.to_str().unwrap(), "bee", &options, count_bees_callback);
+    let elapsed = started.elapsed();
+    fs::remove_file(&path).unwrap();
+
+    assert_eq!(SearchStatusCode::Success, result_code);
+    assert_eq!(20_000, *BEE_COUNT.lock().unwrap());
+    assert!(
+        elapsed < Duration::from_secs(5),
+        "Searching took {:?}",
+        elapsed
+    );
+}
+
+#[cfg(feature = "pcre2")]
+#[test]
+fn test_pcre2_engine_error_is_not_reported_as_callback_error() {
+    // a code point above 255 only compiles in UTF mode, so it can't search the line that isn't UTF-8
+    let options = options_with_engine(RegexEngine::Pcre2);
+
+    let result_code = search(
+        NOT_UTF8_FILE_NAME,
+        r"\x{65e5}",
+        &options,
+        always_succeeding_callback,
+    );
+
+    assert_eq!(SearchStatusCode::ErrorFromRipgrep, result_code);
+}
+
+#[cfg(feature = "pcre2")]
+#[test]
+fn test_auto_engine_searches_directory_with_binary_files() {
+    let options = SearchOptions {
+        count: CountMode::Lines as c_int,
+        ..options_with_engine(RegexEngine::Auto)
+    };
+
+    let result_code = search(
+        SHERLOCK_DIR_PATH,
+        "(?<=Sherlock )Holmes",
+        &options,
+        record_binary_callback,
+    );
+
+    // only sherlock-nul.txt has any matches; the rest are compressed
+    assert_eq!(SearchStatusCode::Success, result_code);
+    assert_eq!(vec![4], *BINARY_COUNTS.lock().unwrap());
+}
+
+#[cfg(not(feature = "pcre2"))]
+#[test]
+fn test_search_with_pcre2_engine_returns_engine_unavailable_error_code_without_feature() {
+    let filename = as_cstring(BEE_MOVIE_FILE_NAME);
+    let search_pattern = as_cstring(LOOK_BEHIND_PATTERN);
+    let options = options_with_engine(RegexEngine::Pcre2);
+
+    let result_code = search_path_with_options(
+        filename.as_ptr(),
+        search_pattern.as_ptr(),
+        &options,
+        Some(always_succeeding_callback),
+    );
+
+    assert_eq!(SearchStatusCode::ErrorEngineUnavailable, result_code);
+}
+
+#[cfg(not(feature = "pcre2"))]
+#[test]
+fn test_search_for_look_behind_with_auto_engine_returns_bad_pattern_error_code_without_feature() {
+    let filename = as_cstring(BEE_MOVIE_FILE_NAME);
+    let search_pattern = as_cstring(LOOK_BEHIND_PATTERN);
+    let options = options_with_engine(RegexEngine::Auto);
+
+    let result_code = search_path_with_options(
+        filename.as_ptr(),
+        search_pattern.as_ptr(),
+        &options,
+        Some(always_succeeding_callback),
+    );
+
+    assert_eq!(SearchStatusCode::ErrorBadPattern, result_code);
+}
+
+extern "C" fn always_succeeding_callback(_: SearchResult) -> bool {
+    true
+}
+
+// each test records into its own list, so tests running concurrently don't interfere
+#[cfg(feature = "pcre2")]
+static PCRE2_LINES: Mutex<Vec<c_int>> = Mutex::new(Vec::new());
+#[cfg(feature = "pcre2")]
+static AUTO_LINES: Mutex<Vec<c_int>> = Mutex::new(Vec::new());
+#[cfg(feature = "pcre2")]
+static NOT_UTF8_LINES: Mutex<Vec<c_int>> = Mutex::new(Vec::new());
+#[cfg(feature = "pcre2")]
+static NOT_UTF8_MULTI_LINE_LINES: Mutex<Vec<c_int>> = Mutex::new(Vec::new());
+#[cfg(feature = "pcre2")]
+static BEE_COUNT: Mutex<usize> = Mutex::new(0);
+#[cfg(feature = "pcre2")]
+static BINARY_COUNTS: Mutex<Vec<i64>> = Mutex::new(Vec::new());
+
+#[cfg(feature = "pcre2")]
+extern "C" fn record_pcre2_callback(result: SearchResult) -> bool {
+    PCRE2_LINES.lock().unwrap().push(result.line_number);
+    true
+}
+
+#[cfg(feature = "pcre2")]
+extern "C" fn record_auto_callback(result: SearchResult) -> bool {
+    AUTO_LINES.lock().unwrap().push(result.line_number);
+    true
+}
+
+#[cfg(feature = "pcre2")]
+extern "C" fn record_not_utf8_callback(result: SearchResult) -> bool {
+    NOT_UTF8_LINES.lock().unwrap().push(result.line_number);
+    true
+}
+
+#[cfg(feature = "pcre2")]
+extern "C" fn record_not_utf8_multi_line_callback(result: SearchResult) -> bool {
+    NOT_UTF8_MULTI_LINE_LINES
+        .lock()
+        .unwrap()
+        .push(result.line_number);
+    true
+}
+
+#[cfg(feature = "pcre2")]
+extern "C" fn count_bees_callback(_result: SearchResult) -> bool {
+    *BEE_COUNT.lock().unwrap() += 1;
+    true
+}
+
+#[cfg(feature = "pcre2")]
+extern "C" fn record_binary_callback(result: SearchResult) -> bool {
+    BINARY_COUNTS
+        .lock()
+        .unwrap()
+        .push(result.num_matching_lines);
+    true
+}
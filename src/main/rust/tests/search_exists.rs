@@ -0,0 +1,80 @@
+use std::ptr;
+
+use super::*;
+
+fn exists(filename: &str, pattern: &str, options: &SearchOptions) -> SearchStatusCode {
+    let filename = as_cstring(filename);
+    let search_pattern = as_cstring(pattern);
+    search_path_exists(filename.as_ptr(), search_pattern.as_ptr(), options)
+}
+
+#[test]
+fn test_match_in_file_is_found() {
+    let result_code = exists(BEE_MOVIE_FILE_NAME, "graduation", &SearchOptions::default());
+
+    assert_eq!(SearchStatusCode::Success, result_code);
+}
+
+#[test]
+fn test_missing_match_in_file_is_not_found() {
+    let result_code = exists(
+        BEE_MOVIE_FILE_NAME,
+        "not in the bee movie",
+        &SearchOptions::default(),
+    );
+
+    assert_eq!(SearchStatusCode::NotFound, result_code);
+}
+
+#[test]
+fn test_match_anywhere_in_dir_is_found() {
+    // only the data directory mentions him
+    let result_code = exists(
+        RESOURCES_DIR_PATH,
+        "Roger Squires",
+        &SearchOptions::default(),
+    );
+
+    assert_eq!(SearchStatusCode::Success, result_code);
+}
+
+#[test]
+fn test_missing_match_in_dir_is_not_found() {
+    let result_code = exists(
+        RESOURCES_DIR_PATH,
+        "not in any test resource",
+        &SearchOptions::default(),
+    );
+
+    assert_eq!(SearchStatusCode::NotFound, result_code);
+}
+
+#[test]
+fn test_existence_check_uses_search_options() {
+    let options = SearchOptions {
+        normalization: NormalizationForm::Nfc as c_int,
+        fold_diacritics: 1,
+        ..SearchOptions::default()
+    };
+
+    let result_code = exists(BEE_MOVIE_FILE_NAME, "gráduation", &options);
+
+    assert_eq!(SearchStatusCode::Success, result_code);
+}
+
+#[test]
+fn test_existence_check_with_bad_pattern_returns_bad_pattern_error_code() {
+    let result_code = exists(BEE_MOVIE_FILE_NAME, "(", &SearchOptions::default());
+
+    assert_eq!(SearchStatusCode::ErrorBadPattern, result_code);
+}
+
+#[test]
+fn test_existence_check_of_missing_file_returns_could_not_open_file_error_code() {
+    let filename = as_cstring("src/test/resources/not_a_file.txt");
+    let search_pattern = as_cstring("bee");
+
+    let result_code = search_path_exists(filename.as_ptr(), search_pattern.as_ptr(), ptr::null());
+
+    assert_eq!(SearchStatusCode::ErrorCouldNotOpenFile, result_code);
+}
@@ -1,9 +1,12 @@
+// these tests exercise the deprecated entry points, which must keep working
+#![allow(deprecated)]
+
 use std::ffi::*;
 use std::ptr;
 
 use super::*;
 
-const BEE_MOVIE_FILE_NAME: &'static str = "src/test/resources/bee_movie.txt";
+const BEE_MOVIE_FILE_NAME: &str = "src/test/resources/bee_movie.txt";
 
 fn as_cstring(text: &str) -> CString {
     let error = format!("Could not represent {:?} as a CString", text);
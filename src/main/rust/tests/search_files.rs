@@ -0,0 +1,133 @@
+use std::ffi::CStr;
+use std::os::raw::c_char;
+use std::ptr;
+use std::sync::Mutex;
+
+use super::*;
+
+fn list(
+    filename: &str,
+    pattern: &str,
+    mode: FileListMode,
+    callback: FilePathCallbackFn,
+) -> SearchStatusCode {
+    let filename = as_cstring(filename);
+    let search_pattern = as_cstring(pattern);
+    search_path_files(
+        filename.as_ptr(),
+        search_pattern.as_ptr(),
+        ptr::null(),
+        mode as c_int,
+        Some(callback),
+    )
+}
+
+fn path_of(path: *const c_char) -> String {
+    unsafe { CStr::from_ptr(path) }
+        .to_string_lossy()
+        .into_owned()
+}
+
+#[test]
+fn test_files_with_matches_lists_each_matching_file_once() {
+    let result_code = list(
+        SHERLOCK_DIR_PATH,
+        "Roger Squires",
+        FileListMode::WithMatches,
+        record_with_matches_callback,
+    );
+
+    assert_eq!(SearchStatusCode::Success, result_code);
+    assert_eq!(
+        vec![format!("{}/sherlock-nul.txt", SHERLOCK_DIR_PATH)],
+        *WITH_MATCHES.lock().unwrap(),
+        "Only the uncompressed copy of Sherlock Holmes mentions Roger Squires in plain text"
+    );
+}
+
+#[test]
+fn test_files_without_match_lists_every_other_file() {
+    let result_code = list(
+        SHERLOCK_DIR_PATH,
+        "Roger Squires",
+        FileListMode::WithoutMatch,
+        record_without_match_callback,
+    );
+
+    assert_eq!(SearchStatusCode::Success, result_code);
+    let mut paths = WITHOUT_MATCH.lock().unwrap().clone();
+    paths.sort();
+    let expected: Vec<String> = ["br", "bz2", "gz", "lz4", "lzma", "xz", "zst"]
+        .iter()
+        .map(|extension| format!("{}/sherlock.{}", SHERLOCK_DIR_PATH, extension))
+        .collect();
+    assert_eq!(expected, paths);
+}
+
+#[test]
+fn test_single_file_with_a_match_is_listed() {
+    let result_code = list(
+        BEE_MOVIE_FILE_NAME,
+        "graduation",
+        FileListMode::WithMatches,
+        record_single_file_callback,
+    );
+
+    assert_eq!(SearchStatusCode::Success, result_code);
+    assert_eq!(
+        vec![BEE_MOVIE_FILE_NAME.to_string()],
+        *SINGLE_FILE.lock().unwrap()
+    );
+}
+
+#[test]
+fn test_listing_returns_callback_error_code_when_callback_returns_false() {
+    let result_code = list(
+        BEE_MOVIE_FILE_NAME,
+        "graduation",
+        FileListMode::WithMatches,
+        always_failing_callback,
+    );
+
+    assert_eq!(SearchStatusCode::ErrorFromCallback, result_code);
+}
+
+#[test]
+fn test_listing_without_callback_returns_missing_callback_error_code() {
+    let filename = as_cstring(BEE_MOVIE_FILE_NAME);
+    let search_pattern = as_cstring("graduation");
+
+    let result_code = search_path_files(
+        filename.as_ptr(),
+        search_pattern.as_ptr(),
+        ptr::null(),
+        FileListMode::WithMatches as c_int,
+        None,
+    );
+
+    assert_eq!(SearchStatusCode::MissingCallback, result_code);
+}
+
+extern "C" fn always_failing_callback(_: *const c_char) -> bool {
+    false
+}
+
+// each test records into its own list, so tests running concurrently don't interfere
+static WITH_MATCHES: Mutex<Vec<String>> = Mutex::new(Vec::new());
+static WITHOUT_MATCH: Mutex<Vec<String>> = Mutex::new(Vec::new());
+static SINGLE_FILE: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+extern "C" fn record_with_matches_callback(path: *const c_char) -> bool {
+    WITH_MATCHES.lock().unwrap().push(path_of(path));
+    true
+}
+
+extern "C" fn record_without_match_callback(path: *const c_char) -> bool {
+    WITHOUT_MATCH.lock().unwrap().push(path_of(path));
+    true
+}
+
+extern "C" fn record_single_file_callback(path: *const c_char) -> bool {
+    SINGLE_FILE.lock().unwrap().push(path_of(path));
+    true
+}
@@ -0,0 +1,100 @@
+use std::os::raw::c_int;
+use std::ptr;
+use std::sync::Mutex;
+
+use super::*;
+
+fn search_bee_movie_fuzzily(
+    text: &str,
+    max_distance: c_int,
+    options: &SearchOptions,
+    callback: SearchResultCallbackFn,
+) -> SearchStatusCode {
+    let filename = as_cstring(BEE_MOVIE_FILE_NAME);
+    let search_text = as_cstring(text);
+    search_path_fuzzy(
+        filename.as_ptr(),
+        search_text.as_ptr(),
+        max_distance,
+        options,
+        Some(callback),
+    )
+}
+
+#[test]
+fn test_misspelled_name_finds_lines_within_distance() {
+    let result_code = search_bee_movie_fuzzily(
+        "Barry Bensen",
+        1,
+        &SearchOptions::default(),
+        record_misspelled_callback,
+    );
+
+    assert_eq!(SearchStatusCode::Success, result_code);
+    assert_eq!(
+        vec![(195, 1), (223, 1), (279, 1), (281, 1)],
+        *MISSPELLED.lock().unwrap(),
+        "Every line with \"Barry Benson\" is one substitution away from \"Barry Bensen\""
+    );
+}
+
+#[test]
+fn test_exact_occurrence_has_no_distance() {
+    let options = SearchOptions {
+        only_matching: 1,
+        ..SearchOptions::default()
+    };
+
+    let result_code = search_bee_movie_fuzzily("Barry Benson", 2, &options, record_exact_callback);
+
+    assert_eq!(SearchStatusCode::Success, result_code);
+    let results = EXACT.lock().unwrap();
+    assert!(results.contains(&(195, 0, "Barry Benson".to_string())));
+}
+
+#[test]
+fn test_search_with_too_many_edits_returns_bad_pattern_error_code() {
+    let result_code =
+        search_bee_movie_fuzzily("bee", 3, &SearchOptions::default(), record_nothing_callback);
+
+    assert_eq!(SearchStatusCode::ErrorBadPattern, result_code);
+}
+
+#[test]
+fn test_search_for_null_text_returns_missing_search_text_error_code() {
+    let filename = as_cstring(BEE_MOVIE_FILE_NAME);
+
+    let result_code = search_path_fuzzy(
+        filename.as_ptr(),
+        ptr::null(),
+        1,
+        ptr::null(),
+        Some(record_nothing_callback),
+    );
+
+    assert_eq!(SearchStatusCode::MissingSearchText, result_code);
+}
+
+// each test records into its own list, so tests running concurrently don't interfere
+static MISSPELLED: Mutex<Vec<(c_int, c_int)>> = Mutex::new(Vec::new());
+static EXACT: Mutex<Vec<(c_int, c_int, String)>> = Mutex::new(Vec::new());
+
+extern "C" fn record_misspelled_callback(result: SearchResult) -> bool {
+    MISSPELLED
+        .lock()
+        .unwrap()
+        .push((result.line_number, result.edit_distance));
+    true
+}
+
+extern "C" fn record_exact_callback(result: SearchResult) -> bool {
+    EXACT
+        .lock()
+        .unwrap()
+        .push((result.line_number, result.edit_distance, text_of(&result)));
+    true
+}
+
+extern "C" fn record_nothing_callback(_: SearchResult) -> bool {
+    true
+}
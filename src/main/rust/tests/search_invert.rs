@@ -0,0 +1,120 @@
+use std::fs;
+use std::os::raw::{c_char, c_int};
+use std::sync::Mutex;
+
+use super::*;
+
+fn inverted() -> SearchOptions {
+    SearchOptions {
+        invert_match: 1,
+        ..SearchOptions::default()
+    }
+}
+
+fn bee_movie_line_numbers_without(text: &str) -> Vec<c_int> {
+    fs::read_to_string(BEE_MOVIE_FILE_NAME)
+        .expect("Could not read the Bee Movie script")
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.contains(text))
+        .map(|(index, _)| index as c_int + 1)
+        .collect()
+}
+
+#[test]
+fn test_inverted_search_reports_every_line_that_does_not_match() {
+    let filename = as_cstring(BEE_MOVIE_FILE_NAME);
+    let search_pattern = as_cstring("graduation");
+
+    let result_code = search_path_with_options(
+        filename.as_ptr(),
+        search_pattern.as_ptr(),
+        &inverted(),
+        Some(record_inverted_callback),
+    );
+
+    assert_eq!(SearchStatusCode::Success, result_code);
+    let results = INVERTED.lock().unwrap();
+    assert_eq!(
+        bee_movie_line_numbers_without("graduation"),
+        results.iter().map(|(line, _)| *line).collect::<Vec<_>>(),
+        "An inverted search should report exactly the lines which do not match"
+    );
+    assert!(
+        results
+            .iter()
+            .all(|(_, kind)| *kind == SearchResultKind::InvertedMatch),
+        "Every line reported by an inverted search should be flagged as a non-match"
+    );
+}
+
+#[test]
+fn test_inverted_dictionary_search_reports_no_pattern_index() {
+    let filename = as_cstring(BEE_MOVIE_FILE_NAME);
+    let literals = [as_cstring("graduation")];
+    let pointers: Vec<*const c_char> = literals.iter().map(|l| l.as_ptr()).collect();
+
+    let result_code = search_path_literals(
+        filename.as_ptr(),
+        pointers.as_ptr(),
+        pointers.len() as c_int,
+        &inverted(),
+        Some(record_inverted_literal_callback),
+    );
+
+    assert_eq!(SearchStatusCode::Success, result_code);
+    let pattern_indices = INVERTED_LITERALS.lock().unwrap();
+    assert_eq!(
+        bee_movie_line_numbers_without("graduation").len(),
+        pattern_indices.len()
+    );
+    assert!(
+        pattern_indices.iter().all(|&index| index == -1),
+        "No dictionary entry matches a line reported by an inverted search"
+    );
+}
+
+#[test]
+fn test_matches_are_flagged_as_matches_by_default() {
+    let filename = as_cstring(BEE_MOVIE_FILE_NAME);
+    let search_pattern = as_cstring("graduation");
+
+    let result_code = search_path_with_options(
+        filename.as_ptr(),
+        search_pattern.as_ptr(),
+        &SearchOptions::default(),
+        Some(record_match_callback),
+    );
+
+    assert_eq!(SearchStatusCode::Success, result_code);
+    assert_eq!(
+        vec![(13, SearchResultKind::Match)],
+        *MATCHES.lock().unwrap()
+    );
+}
+
+// each test records into its own list, so tests running concurrently don't interfere
+static INVERTED: Mutex<Vec<(c_int, SearchResultKind)>> = Mutex::new(Vec::new());
+static INVERTED_LITERALS: Mutex<Vec<c_int>> = Mutex::new(Vec::new());
+static MATCHES: Mutex<Vec<(c_int, SearchResultKind)>> = Mutex::new(Vec::new());
+
+extern "C" fn record_inverted_callback(result: SearchResult) -> bool {
+    INVERTED
+        .lock()
+        .unwrap()
+        .push((result.line_number, result.kind));
+    true
+}
+
+extern "C" fn record_inverted_literal_callback(result: SearchResult) -> bool {
+    INVERTED_LITERALS.lock().unwrap().push(result.pattern_index);
+    true
+}
+
+extern "C" fn record_match_callback(result: SearchResult) -> bool {
+    MATCHES
+        .lock()
+        .unwrap()
+        .push((result.line_number, result.kind));
+    true
+}
@@ -0,0 +1,200 @@
+use std::os::raw::c_int;
+use std::sync::Mutex;
+
+use super::*;
+
+const NUL_RECORDS_FILE_NAME: &str = "src/test/resources/nul_records.dat";
+
+#[test]
+fn test_end_anchor_matches_before_crlf() {
+    let options = SearchOptions {
+        line_terminator: LineTerminatorKind::Crlf as c_int,
+        ..SearchOptions::default()
+    };
+
+    let result_code = search(CRLF_FILE_NAME, "line$", &options, record_crlf_callback);
+
+    assert_eq!(SearchStatusCode::Success, result_code);
+    assert_eq!(vec![1, 2, 3], *CRLF_LINES.lock().unwrap());
+}
+
+#[test]
+fn test_end_anchor_does_not_match_before_crlf_by_default() {
+    let result_code = search(
+        CRLF_FILE_NAME,
+        "line$",
+        &SearchOptions::default(),
+        record_lf_callback,
+    );
+
+    assert_eq!(SearchStatusCode::Success, result_code);
+    assert!(LF_LINES.lock().unwrap().is_empty());
+}
+
+#[test]
+fn test_nul_terminated_records_are_reported_whole() {
+    let options = SearchOptions {
+        line_terminator: LineTerminatorKind::Byte as c_int,
+        line_terminator_byte: b'\0',
+        ..SearchOptions::default()
+    };
+
+    let result_code = search(
+        NUL_RECORDS_FILE_NAME,
+        "gamma",
+        &options,
+        record_nul_callback,
+    );
+
+    assert_eq!(SearchStatusCode::Success, result_code);
+    assert_eq!(
+        vec![(3, b"gamma\nthree\0".to_vec())],
+        *NUL_RECORDS.lock().unwrap(),
+        "The third NUL-terminated record should be reported whole, even though it contains a newline"
+    );
+}
+
+#[test]
+fn test_classes_do_not_match_across_nul_terminated_records() {
+    let options = SearchOptions {
+        line_terminator: LineTerminatorKind::Byte as c_int,
+        line_terminator_byte: b'\0',
+        ..SearchOptions::default()
+    };
+
+    let result_code = search(
+        NUL_RECORDS_FILE_NAME,
+        r"one\Wbeta",
+        &options,
+        record_nul_class_callback,
+    );
+
+    assert_eq!(SearchStatusCode::Success, result_code);
+    assert!(NUL_CLASS_RECORDS.lock().unwrap().is_empty());
+}
+
+#[cfg(feature = "pcre2")]
+#[test]
+fn test_pcre2_classes_do_not_match_across_nul_terminated_records() {
+    let options = SearchOptions {
+        engine: RegexEngine::Pcre2 as c_int,
+        line_terminator: LineTerminatorKind::Byte as c_int,
+        line_terminator_byte: b'\0',
+        ..SearchOptions::default()
+    };
+
+    let result_code = search(
+        NUL_RECORDS_FILE_NAME,
+        r"one\Wbeta",
+        &options,
+        record_pcre2_nul_class_callback,
+    );
+
+    assert_eq!(SearchStatusCode::Success, result_code);
+    assert!(PCRE2_NUL_CLASS_RECORDS.lock().unwrap().is_empty());
+}
+
+#[test]
+fn test_end_anchor_spans_match_before_nul_terminator() {
+    let options = SearchOptions {
+        line_terminator: LineTerminatorKind::Byte as c_int,
+        line_terminator_byte: b'\0',
+        report_match_spans: 1,
+        ..SearchOptions::default()
+    };
+
+    let result_code = search(
+        NUL_RECORDS_FILE_NAME,
+        "two$",
+        &options,
+        record_nul_spans_callback,
+    );
+
+    assert_eq!(SearchStatusCode::Success, result_code);
+    assert_eq!(
+        vec![(2, 6, vec![(5, 8)])],
+        *NUL_SPANS.lock().unwrap(),
+        "The match's column and span should be found without the record's NUL terminator"
+    );
+}
+
+#[test]
+fn test_only_matching_end_anchor_matches_before_nul_terminator() {
+    let options = SearchOptions {
+        line_terminator: LineTerminatorKind::Byte as c_int,
+        line_terminator_byte: b'\0',
+        only_matching: 1,
+        ..SearchOptions::default()
+    };
+
+    let result_code = search(
+        NUL_RECORDS_FILE_NAME,
+        "t[a-z]+$",
+        &options,
+        record_nul_only_matching_callback,
+    );
+
+    assert_eq!(SearchStatusCode::Success, result_code);
+    assert_eq!(
+        vec![(2, b"two".to_vec()), (3, b"three".to_vec())],
+        *NUL_ONLY_MATCHING.lock().unwrap()
+    );
+}
+
+// each test records into its own list, so tests running concurrently don't interfere
+static CRLF_LINES: Mutex<Vec<c_int>> = Mutex::new(Vec::new());
+static LF_LINES: Mutex<Vec<c_int>> = Mutex::new(Vec::new());
+static NUL_RECORDS: Mutex<Vec<(c_int, Vec<u8>)>> = Mutex::new(Vec::new());
+static NUL_CLASS_RECORDS: Mutex<Vec<c_int>> = Mutex::new(Vec::new());
+#[cfg(feature = "pcre2")]
+static PCRE2_NUL_CLASS_RECORDS: Mutex<Vec<c_int>> = Mutex::new(Vec::new());
+static NUL_SPANS: Mutex<Vec<(c_int, c_int, Vec<Span>)>> = Mutex::new(Vec::new());
+static NUL_ONLY_MATCHING: Mutex<Vec<(c_int, Vec<u8>)>> = Mutex::new(Vec::new());
+
+extern "C" fn record_crlf_callback(result: SearchResult) -> bool {
+    CRLF_LINES.lock().unwrap().push(result.line_number);
+    true
+}
+
+extern "C" fn record_lf_callback(result: SearchResult) -> bool {
+    LF_LINES.lock().unwrap().push(result.line_number);
+    true
+}
+
+extern "C" fn record_nul_callback(result: SearchResult) -> bool {
+    NUL_RECORDS
+        .lock()
+        .unwrap()
+        .push((result.line_number, bytes_of(&result)));
+    true
+}
+
+extern "C" fn record_nul_class_callback(result: SearchResult) -> bool {
+    NUL_CLASS_RECORDS.lock().unwrap().push(result.line_number);
+    true
+}
+
+#[cfg(feature = "pcre2")]
+extern "C" fn record_pcre2_nul_class_callback(result: SearchResult) -> bool {
+    PCRE2_NUL_CLASS_RECORDS
+        .lock()
+        .unwrap()
+        .push(result.line_number);
+    true
+}
+
+extern "C" fn record_nul_spans_callback(result: SearchResult) -> bool {
+    NUL_SPANS
+        .lock()
+        .unwrap()
+        .push((result.line_number, result.column, spans_of(&result)));
+    true
+}
+
+extern "C" fn record_nul_only_matching_callback(result: SearchResult) -> bool {
+    NUL_ONLY_MATCHING
+        .lock()
+        .unwrap()
+        .push((result.line_number, bytes_of(&result)));
+    true
+}
@@ -0,0 +1,143 @@
+use std::ffi::*;
+use std::os::raw::{c_char, c_int};
+use std::ptr;
+use std::sync::Mutex;
+
+use super::*;
+
+fn as_cstrings(texts: &[&str]) -> Vec<CString> {
+    texts.iter().map(|text| as_cstring(text)).collect()
+}
+
+fn as_pointers(texts: &[CString]) -> Vec<*const c_char> {
+    texts.iter().map(|text| text.as_ptr()).collect()
+}
+
+#[test]
+fn test_search_for_dictionary_without_error() {
+    let filename = as_cstring(BEE_MOVIE_FILE_NAME);
+    let literals = as_cstrings(&["bee", "honey", "pollen"]);
+    let pointers = as_pointers(&literals);
+
+    let result_code = search_path_literals(
+        filename.as_ptr(),
+        pointers.as_ptr(),
+        pointers.len() as c_int,
+        ptr::null(),
+        Some(always_succeeding_callback),
+    );
+
+    assert_eq!(SearchStatusCode::Success, result_code,
+            "When the callback returns true to indicate success, the extern search_path_literals function should always return {:?}", SearchStatusCode::Success);
+}
+
+#[test]
+fn test_search_for_dictionary_returns_callback_error_code_when_callback_returns_false() {
+    let filename = as_cstring(BEE_MOVIE_FILE_NAME);
+    let literals = as_cstrings(&["bee", "honey", "pollen"]);
+    let pointers = as_pointers(&literals);
+
+    let result_code = search_path_literals(
+        filename.as_ptr(),
+        pointers.as_ptr(),
+        pointers.len() as c_int,
+        ptr::null(),
+        Some(always_failing_callback),
+    );
+
+    assert_eq!(SearchStatusCode::ErrorFromCallback, result_code,
+            "When the callback returns false to indicate an error, the extern search_path_literals function should always return {:?}", SearchStatusCode::ErrorFromCallback);
+}
+
+#[test]
+fn test_search_for_null_dictionary_returns_missing_search_text_error_code() {
+    let filename = as_cstring(BEE_MOVIE_FILE_NAME);
+
+    let result_code = search_path_literals(
+        filename.as_ptr(),
+        ptr::null(),
+        0,
+        ptr::null(),
+        Some(always_succeeding_callback),
+    );
+
+    assert_eq!(
+        SearchStatusCode::MissingSearchText,
+        result_code,
+        "When passing a null dictionary, the extern search_path_literals function should always return {:?}",
+        SearchStatusCode::MissingSearchText
+    );
+}
+
+#[test]
+fn test_search_reports_which_dictionary_entry_matched() {
+    let filename = as_cstring(BEE_MOVIE_FILE_NAME);
+    // only the second entry is in the script, and only on line 13
+    let literals = as_cstrings(&["not in the bee movie", "graduation"]);
+    let pointers = as_pointers(&literals);
+
+    let result_code = search_path_literals(
+        filename.as_ptr(),
+        pointers.as_ptr(),
+        pointers.len() as c_int,
+        ptr::null(),
+        Some(record_graduation_callback),
+    );
+
+    assert_eq!(SearchStatusCode::Success, result_code);
+    assert_eq!(
+        vec![(13, 1)],
+        *GRADUATIONS.lock().unwrap(),
+        "Expected exactly one match for the dictionary entry at index 1, on line 13"
+    );
+}
+
+#[test]
+fn test_search_dir_for_dictionary() {
+    let filename = as_cstring(SHERLOCK_DIR_PATH);
+    let literals = as_cstrings(&["Roger Squires"]);
+    let pointers = as_pointers(&literals);
+
+    let result_code = search_path_literals(
+        filename.as_ptr(),
+        pointers.as_ptr(),
+        pointers.len() as c_int,
+        ptr::null(),
+        Some(record_squires_callback),
+    );
+
+    assert_eq!(SearchStatusCode::Success, result_code);
+    assert_eq!(
+        vec![(25, 0)],
+        *SQUIRES.lock().unwrap(),
+        "Expected exactly one match for \"Roger Squires\" in Sherlock Holmes, on line 25"
+    );
+}
+
+extern "C" fn always_succeeding_callback(_: SearchResult) -> bool {
+    true
+}
+
+extern "C" fn always_failing_callback(_: SearchResult) -> bool {
+    false
+}
+
+// each test records into its own list, so tests running concurrently don't interfere
+static GRADUATIONS: Mutex<Vec<(c_int, c_int)>> = Mutex::new(Vec::new());
+static SQUIRES: Mutex<Vec<(c_int, c_int)>> = Mutex::new(Vec::new());
+
+extern "C" fn record_graduation_callback(result: SearchResult) -> bool {
+    GRADUATIONS
+        .lock()
+        .unwrap()
+        .push((result.line_number, result.pattern_index));
+    true
+}
+
+extern "C" fn record_squires_callback(result: SearchResult) -> bool {
+    SQUIRES
+        .lock()
+        .unwrap()
+        .push((result.line_number, result.pattern_index));
+    true
+}
@@ -0,0 +1,110 @@
+use std::os::raw::{c_char, c_int};
+use std::sync::Mutex;
+
+use super::*;
+
+fn with_match_spans() -> SearchOptions {
+    SearchOptions {
+        report_match_spans: 1,
+        ..SearchOptions::default()
+    }
+}
+
+#[test]
+fn test_every_match_on_a_line_is_reported() {
+    let result_code = search_bee_movie("Three days", &with_match_spans(), record_spans_callback);
+
+    assert_eq!(SearchStatusCode::Success, result_code);
+    let results = SPANS.lock().unwrap();
+    assert_eq!(
+        Some(&(13, vec![(62, 72), (131, 141)])),
+        results.iter().find(|(line, _)| *line == 13),
+        "Both occurrences on line 13 should be reported, relative to the start of the line"
+    );
+}
+
+#[test]
+fn test_dictionary_matches_are_reported() {
+    let filename = as_cstring(BEE_MOVIE_FILE_NAME);
+    let literals = [as_cstring("graduation"), as_cstring("Special day")];
+    let pointers: Vec<*const c_char> = literals.iter().map(|l| l.as_ptr()).collect();
+
+    let result_code = search_path_literals(
+        filename.as_ptr(),
+        pointers.as_ptr(),
+        pointers.len() as c_int,
+        &with_match_spans(),
+        Some(record_literal_spans_callback),
+    );
+
+    assert_eq!(SearchStatusCode::Success, result_code);
+    assert_eq!(
+        vec![(13, vec![(10, 21), (23, 33)])],
+        *LITERAL_SPANS.lock().unwrap()
+    );
+}
+
+#[test]
+fn test_inverted_results_have_no_spans() {
+    let options = SearchOptions {
+        invert_match: 1,
+        ..with_match_spans()
+    };
+
+    let result_code = search_bee_movie("graduation", &options, record_inverted_spans_callback);
+
+    assert_eq!(SearchStatusCode::Success, result_code);
+    let results = INVERTED_SPANS.lock().unwrap();
+    assert!(!results.is_empty());
+    assert!(results.iter().all(|(_, spans)| spans.is_empty()));
+}
+
+#[test]
+fn test_spans_are_not_reported_by_default() {
+    let result_code = search_bee_movie(
+        "graduation",
+        &SearchOptions::default(),
+        record_default_spans_callback,
+    );
+
+    assert_eq!(SearchStatusCode::Success, result_code);
+    assert_eq!(vec![(13, 0)], *DEFAULT_SPANS.lock().unwrap());
+}
+
+// each test records into its own list, so tests running concurrently don't interfere
+static SPANS: Mutex<Vec<(c_int, Vec<Span>)>> = Mutex::new(Vec::new());
+static LITERAL_SPANS: Mutex<Vec<(c_int, Vec<Span>)>> = Mutex::new(Vec::new());
+static INVERTED_SPANS: Mutex<Vec<(c_int, Vec<Span>)>> = Mutex::new(Vec::new());
+static DEFAULT_SPANS: Mutex<Vec<(c_int, c_int)>> = Mutex::new(Vec::new());
+
+extern "C" fn record_spans_callback(result: SearchResult) -> bool {
+    SPANS
+        .lock()
+        .unwrap()
+        .push((result.line_number, spans_of(&result)));
+    true
+}
+
+extern "C" fn record_literal_spans_callback(result: SearchResult) -> bool {
+    LITERAL_SPANS
+        .lock()
+        .unwrap()
+        .push((result.line_number, spans_of(&result)));
+    true
+}
+
+extern "C" fn record_inverted_spans_callback(result: SearchResult) -> bool {
+    INVERTED_SPANS
+        .lock()
+        .unwrap()
+        .push((result.line_number, spans_of(&result)));
+    true
+}
+
+extern "C" fn record_default_spans_callback(result: SearchResult) -> bool {
+    DEFAULT_SPANS
+        .lock()
+        .unwrap()
+        .push((result.line_number, result.num_match_spans));
+    true
+}
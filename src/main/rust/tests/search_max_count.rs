@@ -0,0 +1,157 @@
+use std::os::raw::c_int;
+use std::sync::Mutex;
+
+use super::*;
+
+#[test]
+fn test_search_stops_after_max_count_matching_lines() {
+    let options = SearchOptions {
+        max_count: 2,
+        report_file_end: 1,
+        ..SearchOptions::default()
+    };
+
+    let result_code = search_bee_movie("Barry", &options, record_truncated_callback);
+
+    assert_eq!(SearchStatusCode::Success, result_code);
+    assert_eq!(
+        vec![
+            (SearchResultKind::Match, 1, -1, false),
+            (SearchResultKind::Match, 2, -1, false),
+            (SearchResultKind::FileEnd, -1, 2, true),
+        ],
+        *TRUNCATED.lock().unwrap(),
+        "Barry is on many more lines, so the file should be reported as truncated after the first two"
+    );
+}
+
+#[test]
+fn test_file_is_not_truncated_when_it_has_no_more_matches() {
+    let options = SearchOptions {
+        max_count: 1,
+        report_file_end: 1,
+        ..SearchOptions::default()
+    };
+
+    let result_code = search_bee_movie("graduation", &options, record_complete_callback);
+
+    assert_eq!(SearchStatusCode::Success, result_code);
+    assert_eq!(
+        vec![
+            (SearchResultKind::Match, 13, -1, false),
+            (SearchResultKind::FileEnd, -1, 1, false),
+        ],
+        *COMPLETE.lock().unwrap()
+    );
+}
+
+#[test]
+fn test_after_context_of_last_match_is_still_reported() {
+    let options = SearchOptions {
+        max_count: 1,
+        before_context: 1,
+        after_context: 1,
+        ..SearchOptions::default()
+    };
+
+    let result_code = search_bee_movie("fuzz", &options, record_context_callback);
+
+    assert_eq!(SearchStatusCode::Success, result_code);
+    assert_eq!(
+        vec![
+            (SearchResultKind::BeforeContext, 5),
+            (SearchResultKind::Match, 6),
+            (SearchResultKind::AfterContext, 7),
+        ],
+        *CONTEXT.lock().unwrap(),
+        "Nothing around the next match should be reported, not even a context break"
+    );
+}
+
+#[test]
+fn test_after_context_of_last_match_includes_lines_which_match() {
+    let options = SearchOptions {
+        max_count: 1,
+        after_context: 3,
+        report_file_end: 1,
+        ..SearchOptions::default()
+    };
+
+    let result_code = search_bee_movie("Barry", &options, record_matching_context_callback);
+
+    assert_eq!(SearchStatusCode::Success, result_code);
+    assert_eq!(
+        vec![
+            (SearchResultKind::Match, 1, -1, false),
+            (SearchResultKind::AfterContext, 2, -1, false),
+            (SearchResultKind::AfterContext, 3, -1, false),
+            (SearchResultKind::AfterContext, 4, -1, false),
+            (SearchResultKind::FileEnd, -1, 1, true),
+        ],
+        *MATCHING_CONTEXT.lock().unwrap(),
+        "Line 2 matches too, but it's in the after-context of line 1, like `rg -m1 -A3`"
+    );
+}
+
+#[test]
+fn test_count_stops_at_max_count() {
+    let options = SearchOptions {
+        count: CountMode::Lines as c_int,
+        max_count: 3,
+        ..SearchOptions::default()
+    };
+
+    let result_code = search_bee_movie("Barry", &options, record_count_callback);
+
+    assert_eq!(SearchStatusCode::Success, result_code);
+    assert_eq!(
+        vec![(SearchResultKind::Count, -1, 3, true)],
+        *COUNT.lock().unwrap()
+    );
+}
+
+type Summary = (SearchResultKind, c_int, i64, bool);
+
+// each test records into its own list, so tests running concurrently don't interfere
+static TRUNCATED: Mutex<Vec<Summary>> = Mutex::new(Vec::new());
+static COMPLETE: Mutex<Vec<Summary>> = Mutex::new(Vec::new());
+static CONTEXT: Mutex<Vec<(SearchResultKind, c_int)>> = Mutex::new(Vec::new());
+static MATCHING_CONTEXT: Mutex<Vec<Summary>> = Mutex::new(Vec::new());
+static COUNT: Mutex<Vec<Summary>> = Mutex::new(Vec::new());
+
+fn summary_of(result: &SearchResult) -> Summary {
+    (
+        result.kind,
+        result.line_number,
+        result.num_matching_lines,
+        result.truncated != 0,
+    )
+}
+
+extern "C" fn record_truncated_callback(result: SearchResult) -> bool {
+    TRUNCATED.lock().unwrap().push(summary_of(&result));
+    true
+}
+
+extern "C" fn record_complete_callback(result: SearchResult) -> bool {
+    COMPLETE.lock().unwrap().push(summary_of(&result));
+    true
+}
+
+extern "C" fn record_context_callback(result: SearchResult) -> bool {
+    CONTEXT
+        .lock()
+        .unwrap()
+        .push((result.kind, result.line_number));
+    true
+}
+
+extern "C" fn record_matching_context_callback(result: SearchResult) -> bool {
+    MATCHING_CONTEXT.lock().unwrap().push(summary_of(&result));
+    true
+}
+
+extern "C" fn record_count_callback(result: SearchResult) -> bool {
+    COUNT.lock().unwrap().push(summary_of(&result));
+    true
+}
@@ -0,0 +1,94 @@
+use std::os::raw::c_int;
+use std::sync::Mutex;
+
+use super::*;
+
+fn with_max_results(max_results: u64) -> SearchOptions {
+    SearchOptions {
+        max_results,
+        ..SearchOptions::default()
+    }
+}
+
+#[test]
+fn test_search_stops_cleanly_at_max_results() {
+    let result_code = search(
+        BEE_MOVIE_FILE_NAME,
+        "bee",
+        &with_max_results(3),
+        record_truncated_callback,
+    );
+
+    assert_eq!(
+        SearchStatusCode::SuccessTruncated,
+        result_code,
+        "There are many more bees, so the search should say it was truncated rather than failing"
+    );
+    assert_eq!(3, TRUNCATED.lock().unwrap().len());
+}
+
+#[test]
+fn test_search_is_not_truncated_when_results_fit() {
+    let result_code = search(
+        BEE_MOVIE_FILE_NAME,
+        "graduation",
+        &with_max_results(1),
+        record_complete_callback,
+    );
+
+    assert_eq!(SearchStatusCode::Success, result_code);
+    assert_eq!(vec![13], *COMPLETE.lock().unwrap());
+}
+
+#[test]
+fn test_max_results_applies_across_every_file_in_a_dir() {
+    // "Hive" is on 6 lines, across 3 files
+    let result_code = search(
+        RESOURCES_DIR_PATH,
+        "Hive",
+        &with_max_results(4),
+        record_dir_callback,
+    );
+
+    assert_eq!(SearchStatusCode::SuccessTruncated, result_code);
+    assert_eq!(4, DIR_RESULTS.lock().unwrap().len());
+}
+
+#[test]
+fn test_exactly_max_results_in_a_dir_is_not_truncated() {
+    let result_code = search(
+        RESOURCES_DIR_PATH,
+        "Hive",
+        &with_max_results(6),
+        record_exact_dir_callback,
+    );
+
+    assert_eq!(SearchStatusCode::Success, result_code);
+    assert_eq!(6, EXACT_DIR_RESULTS.lock().unwrap().len());
+}
+
+// each test records into its own list, so tests running concurrently don't interfere
+static TRUNCATED: Mutex<Vec<c_int>> = Mutex::new(Vec::new());
+static COMPLETE: Mutex<Vec<c_int>> = Mutex::new(Vec::new());
+static DIR_RESULTS: Mutex<Vec<c_int>> = Mutex::new(Vec::new());
+static EXACT_DIR_RESULTS: Mutex<Vec<c_int>> = Mutex::new(Vec::new());
+
+extern "C" fn record_truncated_callback(result: SearchResult) -> bool {
+    TRUNCATED.lock().unwrap().push(result.line_number);
+    true
+}
+
+extern "C" fn record_complete_callback(result: SearchResult) -> bool {
+    COMPLETE.lock().unwrap().push(result.line_number);
+    true
+}
+
+extern "C" fn record_dir_callback(result: SearchResult) -> bool {
+    DIR_RESULTS.lock().unwrap().push(result.line_number);
+    true
+}
+
+extern "C" fn record_exact_dir_callback(result: SearchResult) -> bool {
+    EXACT_DIR_RESULTS.lock().unwrap().push(result.line_number);
+    true
+}
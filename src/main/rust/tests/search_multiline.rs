@@ -0,0 +1,104 @@
+use std::os::raw::c_int;
+use std::sync::Mutex;
+
+use super::*;
+
+#[test]
+fn test_multi_line_match_is_reported_once_with_its_line_range() {
+    let options = SearchOptions {
+        multi_line: 1,
+        ..SearchOptions::default()
+    };
+
+    let result_code = search_bee_movie(r"fuzz gel\?\nA little", &options, record_span_callback);
+
+    assert_eq!(SearchStatusCode::Success, result_code);
+    assert_eq!(
+        vec![(
+            12,
+            13,
+            "Is that fuzz gel?\nA little. Special day, graduation.".to_string()
+        )],
+        *SPANS.lock().unwrap(),
+        "A match spanning lines 12 and 13 should be delivered as one result containing both lines"
+    );
+}
+
+#[test]
+fn test_dot_matches_new_line_in_multi_line_mode() {
+    let options = SearchOptions {
+        multi_line: 1,
+        dot_matches_new_line: 1,
+        ..SearchOptions::default()
+    };
+
+    let result_code = search_bee_movie("fuzz gel..A little", &options, record_dot_callback);
+
+    assert_eq!(SearchStatusCode::Success, result_code);
+    assert_eq!(vec![(12, 13)], *DOT_SPANS.lock().unwrap());
+}
+
+#[test]
+fn test_dot_does_not_match_new_line_by_default() {
+    let options = SearchOptions {
+        multi_line: 1,
+        ..SearchOptions::default()
+    };
+
+    let result_code = search_bee_movie("fuzz gel..A little", &options, record_no_dot_callback);
+
+    assert_eq!(SearchStatusCode::Success, result_code);
+    assert!(NO_DOT_SPANS.lock().unwrap().is_empty());
+}
+
+#[test]
+fn test_single_line_match_ends_on_the_same_line() {
+    let result_code = search_bee_movie(
+        "graduation",
+        &SearchOptions::default(),
+        record_single_line_callback,
+    );
+
+    assert_eq!(SearchStatusCode::Success, result_code);
+    assert_eq!(vec![(13, 13)], *SINGLE_LINE_SPANS.lock().unwrap());
+}
+
+// each test records into its own list, so tests running concurrently don't interfere
+static SPANS: Mutex<Vec<(c_int, c_int, String)>> = Mutex::new(Vec::new());
+static DOT_SPANS: Mutex<Vec<(c_int, c_int)>> = Mutex::new(Vec::new());
+static NO_DOT_SPANS: Mutex<Vec<(c_int, c_int)>> = Mutex::new(Vec::new());
+static SINGLE_LINE_SPANS: Mutex<Vec<(c_int, c_int)>> = Mutex::new(Vec::new());
+
+extern "C" fn record_span_callback(result: SearchResult) -> bool {
+    // only keep the start of the second line, since it goes on for a while
+    let text: String = text_of(&result).chars().take(52).collect();
+    SPANS
+        .lock()
+        .unwrap()
+        .push((result.line_number, result.end_line_number, text));
+    true
+}
+
+extern "C" fn record_dot_callback(result: SearchResult) -> bool {
+    DOT_SPANS
+        .lock()
+        .unwrap()
+        .push((result.line_number, result.end_line_number));
+    true
+}
+
+extern "C" fn record_no_dot_callback(result: SearchResult) -> bool {
+    NO_DOT_SPANS
+        .lock()
+        .unwrap()
+        .push((result.line_number, result.end_line_number));
+    true
+}
+
+extern "C" fn record_single_line_callback(result: SearchResult) -> bool {
+    SINGLE_LINE_SPANS
+        .lock()
+        .unwrap()
+        .push((result.line_number, result.end_line_number));
+    true
+}
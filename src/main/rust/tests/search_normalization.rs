@@ -0,0 +1,193 @@
+use std::os::raw::c_int;
+use std::sync::Mutex;
+
+use super::*;
+
+// "résumé" composed on line 1, decomposed on line 2, without accents on line 3,
+// and "ﬁnal" with a ligature on line 4
+const NORMALIZATION_FILE_NAME: &str = "src/test/resources/normalization.txt";
+
+#[test]
+fn test_composed_and_decomposed_forms_match_each_other() {
+    let options = SearchOptions {
+        normalization: NormalizationForm::Nfc as c_int,
+        report_match_spans: 1,
+        ..SearchOptions::default()
+    };
+
+    let result_code = search(
+        NORMALIZATION_FILE_NAME,
+        "r\u{e9}sum\u{e9}",
+        &options,
+        record_nfc_callback,
+    );
+
+    assert_eq!(SearchStatusCode::Success, result_code);
+    assert_eq!(
+        vec![(1, vec![(17, 25)]), (2, vec![(17, 27)])],
+        *NFC.lock().unwrap(),
+        "Both forms should match, with spans covering the original bytes of each"
+    );
+}
+
+#[test]
+fn test_multi_line_search_matches_normalized_text_across_lines() {
+    let options = SearchOptions {
+        multi_line: 1,
+        normalization: NormalizationForm::Nfc as c_int,
+        report_match_spans: 1,
+        ..SearchOptions::default()
+    };
+
+    let result_code = search(
+        NORMALIZATION_FILE_NAME,
+        "r\u{e9}sum\u{e9} today\nresume",
+        &options,
+        record_nfc_multi_line_callback,
+    );
+
+    assert_eq!(SearchStatusCode::Success, result_code);
+    assert_eq!(
+        vec![(2, 3, vec![(17, 40)])],
+        *NFC_MULTI_LINE.lock().unwrap(),
+        "The decomposed line 2 and line 3 should match together, with the span covering their original bytes"
+    );
+}
+
+#[test]
+fn test_folding_diacritics_finds_accented_text() {
+    let options = SearchOptions {
+        fold_diacritics: 1,
+        ..SearchOptions::default()
+    };
+
+    let result_code = search(
+        NORMALIZATION_FILE_NAME,
+        "resume",
+        &options,
+        record_folded_callback,
+    );
+
+    assert_eq!(SearchStatusCode::Success, result_code);
+    assert_eq!(vec![1, 2, 3], *FOLDED.lock().unwrap());
+}
+
+#[test]
+fn test_compatibility_form_matches_ligatures() {
+    let options = SearchOptions {
+        normalization: NormalizationForm::Nfkc as c_int,
+        ..SearchOptions::default()
+    };
+
+    let result_code = search(
+        NORMALIZATION_FILE_NAME,
+        "final",
+        &options,
+        record_nfkc_callback,
+    );
+
+    assert_eq!(SearchStatusCode::Success, result_code);
+    assert_eq!(vec![4], *NFKC.lock().unwrap());
+}
+
+#[test]
+fn test_compatibility_form_of_metacharacter_is_matched_literally() {
+    let options = SearchOptions {
+        normalization: NormalizationForm::Nfkc as c_int,
+        ..SearchOptions::default()
+    };
+
+    // the fullwidth bracket's compatibility form is `(`, which mustn't open a group
+    let result_code = search(
+        HIVE_JAVA_FILE_NAME,
+        "countWorkers\u{ff08}List",
+        &options,
+        record_nfkc_bracket_callback,
+    );
+
+    assert_eq!(SearchStatusCode::Success, result_code);
+    assert_eq!(vec![2], *NFKC_BRACKET.lock().unwrap());
+}
+
+#[test]
+fn test_compatibility_form_of_wildcard_is_matched_literally() {
+    let options = SearchOptions {
+        normalization: NormalizationForm::Nfkc as c_int,
+        syntax: PatternSyntax::Wildcard as c_int,
+        ..SearchOptions::default()
+    };
+
+    // the fullwidth asterisk's compatibility form is `*`, which mustn't match every line
+    let result_code = search(
+        NORMALIZATION_FILE_NAME,
+        "\u{ff0a}",
+        &options,
+        record_nfkc_wildcard_callback,
+    );
+
+    assert_eq!(SearchStatusCode::Success, result_code);
+    assert!(NFKC_WILDCARD.lock().unwrap().is_empty());
+}
+
+#[test]
+fn test_text_is_not_normalized_by_default() {
+    let result_code = search(
+        NORMALIZATION_FILE_NAME,
+        "r\u{e9}sum\u{e9}",
+        &SearchOptions::default(),
+        record_unnormalized_callback,
+    );
+
+    assert_eq!(SearchStatusCode::Success, result_code);
+    assert_eq!(vec![1], *UNNORMALIZED.lock().unwrap());
+}
+
+// each test records into its own list, so tests running concurrently don't interfere
+static NFC: Mutex<Vec<(c_int, Vec<Span>)>> = Mutex::new(Vec::new());
+static NFC_MULTI_LINE: Mutex<Vec<(c_int, c_int, Vec<Span>)>> = Mutex::new(Vec::new());
+static FOLDED: Mutex<Vec<c_int>> = Mutex::new(Vec::new());
+static NFKC: Mutex<Vec<c_int>> = Mutex::new(Vec::new());
+static NFKC_BRACKET: Mutex<Vec<c_int>> = Mutex::new(Vec::new());
+static NFKC_WILDCARD: Mutex<Vec<c_int>> = Mutex::new(Vec::new());
+static UNNORMALIZED: Mutex<Vec<c_int>> = Mutex::new(Vec::new());
+
+extern "C" fn record_nfc_callback(result: SearchResult) -> bool {
+    NFC.lock()
+        .unwrap()
+        .push((result.line_number, spans_of(&result)));
+    true
+}
+
+extern "C" fn record_nfc_multi_line_callback(result: SearchResult) -> bool {
+    NFC_MULTI_LINE.lock().unwrap().push((
+        result.line_number,
+        result.end_line_number,
+        spans_of(&result),
+    ));
+    true
+}
+
+extern "C" fn record_folded_callback(result: SearchResult) -> bool {
+    FOLDED.lock().unwrap().push(result.line_number);
+    true
+}
+
+extern "C" fn record_nfkc_callback(result: SearchResult) -> bool {
+    NFKC.lock().unwrap().push(result.line_number);
+    true
+}
+
+extern "C" fn record_nfkc_bracket_callback(result: SearchResult) -> bool {
+    NFKC_BRACKET.lock().unwrap().push(result.line_number);
+    true
+}
+
+extern "C" fn record_nfkc_wildcard_callback(result: SearchResult) -> bool {
+    NFKC_WILDCARD.lock().unwrap().push(result.line_number);
+    true
+}
+
+extern "C" fn record_unnormalized_callback(result: SearchResult) -> bool {
+    UNNORMALIZED.lock().unwrap().push(result.line_number);
+    true
+}
@@ -0,0 +1,141 @@
+use std::fs;
+use std::os::raw::{c_char, c_int};
+use std::sync::Mutex;
+
+use super::*;
+
+fn only_matching() -> SearchOptions {
+    SearchOptions {
+        only_matching: 1,
+        ..SearchOptions::default()
+    }
+}
+
+fn bee_movie_offset_of_line(line_number: usize) -> i64 {
+    fs::read_to_string(BEE_MOVIE_FILE_NAME)
+        .expect("Could not read the Bee Movie script")
+        .split_inclusive('\n')
+        .take(line_number - 1)
+        .map(|line| line.len() as i64)
+        .sum()
+}
+
+#[test]
+fn test_each_match_is_its_own_result() {
+    let result_code = search_bee_movie("Three days", &only_matching(), record_each_match_callback);
+
+    assert_eq!(SearchStatusCode::Success, result_code);
+    let line_13_start = bee_movie_offset_of_line(13);
+    let results: Vec<(c_int, c_int, i64, String)> = EACH_MATCH
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|result| result.0 == 13)
+        .cloned()
+        .collect();
+    assert_eq!(
+        vec![
+            (13, 63, line_13_start + 62, "Three days".to_string()),
+            (13, 132, line_13_start + 131, "Three days".to_string()),
+        ],
+        results,
+        "Each match on line 13 should be reported on its own, with only the matched bytes"
+    );
+}
+
+#[test]
+fn test_each_dictionary_match_reports_its_own_pattern_index() {
+    let filename = as_cstring(BEE_MOVIE_FILE_NAME);
+    let literals = [as_cstring("graduation"), as_cstring("Special day")];
+    let pointers: Vec<*const c_char> = literals.iter().map(|l| l.as_ptr()).collect();
+
+    let result_code = search_path_literals(
+        filename.as_ptr(),
+        pointers.as_ptr(),
+        pointers.len() as c_int,
+        &only_matching(),
+        Some(record_literal_callback),
+    );
+
+    assert_eq!(SearchStatusCode::Success, result_code);
+    assert_eq!(
+        vec![
+            (1, "Special day".to_string()),
+            (0, "graduation".to_string())
+        ],
+        *LITERALS.lock().unwrap()
+    );
+}
+
+#[test]
+fn test_captures_are_relative_to_the_match() {
+    let options = SearchOptions {
+        report_captures: 1,
+        ..only_matching()
+    };
+
+    let result_code =
+        search_bee_movie(r"Special (?P<what>\w+)", &options, record_captures_callback);
+
+    assert_eq!(SearchStatusCode::Success, result_code);
+    assert_eq!(vec![(0, 0, 11), (0, 8, 11)], *CAPTURES.lock().unwrap());
+}
+
+#[test]
+fn test_multi_line_match_reports_its_own_line_range() {
+    let options = SearchOptions {
+        multi_line: 1,
+        ..only_matching()
+    };
+
+    let result_code = search_bee_movie(r"gel\?\nA little", &options, record_multi_line_callback);
+
+    assert_eq!(SearchStatusCode::Success, result_code);
+    assert_eq!(
+        vec![(12, 13, "gel?\nA little".to_string())],
+        *MULTI_LINE.lock().unwrap()
+    );
+}
+
+// each test records into its own list, so tests running concurrently don't interfere
+static EACH_MATCH: Mutex<Vec<(c_int, c_int, i64, String)>> = Mutex::new(Vec::new());
+static LITERALS: Mutex<Vec<(c_int, String)>> = Mutex::new(Vec::new());
+static CAPTURES: Mutex<Vec<(c_int, c_int, c_int)>> = Mutex::new(Vec::new());
+static MULTI_LINE: Mutex<Vec<(c_int, c_int, String)>> = Mutex::new(Vec::new());
+
+extern "C" fn record_each_match_callback(result: SearchResult) -> bool {
+    EACH_MATCH.lock().unwrap().push((
+        result.line_number,
+        result.column,
+        result.byte_offset,
+        text_of(&result),
+    ));
+    true
+}
+
+extern "C" fn record_literal_callback(result: SearchResult) -> bool {
+    LITERALS
+        .lock()
+        .unwrap()
+        .push((result.pattern_index, text_of(&result)));
+    true
+}
+
+extern "C" fn record_captures_callback(result: SearchResult) -> bool {
+    let groups =
+        unsafe { std::slice::from_raw_parts(result.captures, result.num_captures as usize) };
+    CAPTURES.lock().unwrap().extend(
+        groups
+            .iter()
+            .map(|group| (group.match_index, group.start, group.end)),
+    );
+    true
+}
+
+extern "C" fn record_multi_line_callback(result: SearchResult) -> bool {
+    MULTI_LINE
+        .lock()
+        .unwrap()
+        .push((result.line_number, result.end_line_number, text_of(&result)));
+    true
+}
@@ -0,0 +1,196 @@
+use std::fs;
+use std::os::raw::c_int;
+use std::sync::Mutex;
+
+use super::*;
+
+// where the given line of the Bee Movie script starts in the file
+fn bee_movie_line_offset(line_number: usize) -> i64 {
+    fs::read_to_string(BEE_MOVIE_FILE_NAME)
+        .expect("Could not read the Bee Movie script")
+        .split_inclusive('\n')
+        .take(line_number - 1)
+        .map(|line| line.len() as i64)
+        .sum()
+}
+
+#[test]
+fn test_long_line_is_replaced_by_preview_centered_on_match() {
+    let options = SearchOptions {
+        max_columns: 20,
+        report_match_spans: 1,
+        ..SearchOptions::default()
+    };
+
+    let result_code = search_bee_movie("graduation", &options, record_preview_callback);
+
+    assert_eq!(SearchStatusCode::Success, result_code);
+    // "graduation" is at bytes 23 to 33 of line 13, so the window around its middle starts at byte 18
+    assert_eq!(
+        vec![Preview {
+            line_number: 13,
+            text: "day, graduation. Nev".to_string(),
+            line_offset: 18,
+            byte_offset: bee_movie_line_offset(13) + 18,
+            line_truncated: true,
+            column: 24,
+            spans: vec![(5, 15)],
+        }],
+        *PREVIEWS.lock().unwrap()
+    );
+}
+
+#[test]
+fn test_each_match_gets_its_own_preview() {
+    let options = SearchOptions {
+        max_columns: 20,
+        preview_each_match: 1,
+        ..SearchOptions::default()
+    };
+
+    let result_code = search_bee_movie("Three days", &options, record_each_preview_callback);
+
+    assert_eq!(SearchStatusCode::Success, result_code);
+    assert_eq!(
+        vec![
+            (57, " it. Three days grad".to_string(), 63),
+            (126, "ard. Three days coll".to_string(), 132),
+        ],
+        *EACH_PREVIEW.lock().unwrap(),
+        "Line 13 has \"Three days\" at bytes 62 and 131, so it should be previewed around each of them"
+    );
+}
+
+#[test]
+fn test_short_line_is_not_truncated() {
+    let options = SearchOptions {
+        max_columns: 100,
+        ..SearchOptions::default()
+    };
+
+    let result_code = search_bee_movie("fuzz gel", &options, record_short_callback);
+
+    assert_eq!(SearchStatusCode::Success, result_code);
+    assert_eq!(
+        vec![(12, "Is that fuzz gel?\n".to_string(), 0, false)],
+        *SHORT.lock().unwrap()
+    );
+}
+
+#[test]
+fn test_long_context_line_is_previewed_from_its_start() {
+    let options = SearchOptions {
+        max_columns: 10,
+        before_context: 1,
+        ..SearchOptions::default()
+    };
+
+    let result_code = search_bee_movie("fuzz gel", &options, record_context_callback);
+
+    assert_eq!(SearchStatusCode::Success, result_code);
+    assert_eq!(
+        vec![
+            (
+                SearchResultKind::BeforeContext,
+                "Hey, Barry".to_string(),
+                true
+            ),
+            (SearchResultKind::Match, " fuzz gel?\n".to_string(), true),
+        ],
+        *CONTEXT.lock().unwrap()
+    );
+}
+
+#[test]
+fn test_line_terminator_does_not_count_toward_max_columns() {
+    let options = SearchOptions {
+        max_columns: 17,
+        before_context: 1,
+        ..SearchOptions::default()
+    };
+
+    let result_code = search_bee_movie("fuzz gel", &options, record_exact_callback);
+
+    assert_eq!(SearchStatusCode::Success, result_code);
+    assert_eq!(
+        vec![
+            (
+                SearchResultKind::BeforeContext,
+                "Hey, Barry.\n".to_string(),
+                false
+            ),
+            (
+                SearchResultKind::Match,
+                "Is that fuzz gel?\n".to_string(),
+                false
+            ),
+        ],
+        *EXACT.lock().unwrap(),
+        "Line 12 is exactly 17 bytes before its line feed, so it should be reported whole"
+    );
+}
+
+#[derive(Debug, PartialEq)]
+struct Preview {
+    line_number: c_int,
+    text: String,
+    line_offset: i64,
+    byte_offset: i64,
+    line_truncated: bool,
+    column: c_int,
+    spans: Vec<(c_int, c_int)>,
+}
+
+// each test records into its own list, so tests running concurrently don't interfere
+static PREVIEWS: Mutex<Vec<Preview>> = Mutex::new(Vec::new());
+static EACH_PREVIEW: Mutex<Vec<(i64, String, c_int)>> = Mutex::new(Vec::new());
+static SHORT: Mutex<Vec<(c_int, String, i64, bool)>> = Mutex::new(Vec::new());
+static CONTEXT: Mutex<Vec<(SearchResultKind, String, bool)>> = Mutex::new(Vec::new());
+static EXACT: Mutex<Vec<(SearchResultKind, String, bool)>> = Mutex::new(Vec::new());
+
+extern "C" fn record_preview_callback(result: SearchResult) -> bool {
+    PREVIEWS.lock().unwrap().push(Preview {
+        line_number: result.line_number,
+        text: text_of(&result),
+        line_offset: result.line_offset,
+        byte_offset: result.byte_offset,
+        line_truncated: result.line_truncated != 0,
+        column: result.column,
+        spans: spans_of(&result),
+    });
+    true
+}
+
+extern "C" fn record_each_preview_callback(result: SearchResult) -> bool {
+    EACH_PREVIEW
+        .lock()
+        .unwrap()
+        .push((result.line_offset, text_of(&result), result.column));
+    true
+}
+
+extern "C" fn record_short_callback(result: SearchResult) -> bool {
+    SHORT.lock().unwrap().push((
+        result.line_number,
+        text_of(&result),
+        result.line_offset,
+        result.line_truncated != 0,
+    ));
+    true
+}
+
+extern "C" fn record_context_callback(result: SearchResult) -> bool {
+    CONTEXT
+        .lock()
+        .unwrap()
+        .push((result.kind, text_of(&result), result.line_truncated != 0));
+    true
+}
+
+extern "C" fn record_exact_callback(result: SearchResult) -> bool {
+    EXACT
+        .lock()
+        .unwrap()
+        .push((result.kind, text_of(&result), result.line_truncated != 0));
+    true
+}
@@ -0,0 +1,135 @@
+use std::os::raw::c_int;
+use std::sync::Mutex;
+
+use super::*;
+
+fn scope_of(result: &SearchResult) -> Option<String> {
+    if result.scope_bytes.is_null() {
+        return None;
+    }
+    let bytes =
+        unsafe { std::slice::from_raw_parts(result.scope_bytes, result.scope_num_bytes as usize) };
+    Some(String::from_utf8_lossy(bytes).into_owned())
+}
+
+#[test]
+fn test_match_is_reported_with_its_enclosing_java_method() {
+    let options = SearchOptions {
+        scope_language: ScopeLanguage::Java as c_int,
+        ..SearchOptions::default()
+    };
+
+    let result_code = search(
+        HIVE_JAVA_FILE_NAME,
+        "isWorking|class",
+        &options,
+        record_java_callback,
+    );
+
+    assert_eq!(SearchStatusCode::Success, result_code);
+    assert_eq!(
+        vec![
+            (1, -1, None),
+            (
+                4,
+                2,
+                Some("    public long countWorkers(List<Bee> bees) {".to_string())
+            ),
+        ],
+        *JAVA_SCOPES.lock().unwrap(),
+        "The class has no scope before it, and the stream filter is inside the countWorkers method"
+    );
+}
+
+#[test]
+fn test_custom_scope_header_takes_the_place_of_the_language() {
+    let scope_header = as_cstring("^Hey, ");
+    let options = SearchOptions {
+        scope_language: ScopeLanguage::Java as c_int,
+        scope_header: scope_header.as_ptr(),
+        ..SearchOptions::default()
+    };
+
+    let result_code = search(
+        BEE_MOVIE_FILE_NAME,
+        "graduation",
+        &options,
+        record_custom_callback,
+    );
+
+    assert_eq!(SearchStatusCode::Success, result_code);
+    assert_eq!(
+        vec![(13, 11, Some("Hey, Barry.".to_string()))],
+        *CUSTOM_SCOPES.lock().unwrap(),
+        "The nearest line starting with \"Hey, \" before the match should be its scope"
+    );
+}
+
+#[test]
+fn test_scope_is_not_reported_by_default() {
+    let result_code = search(
+        HIVE_JAVA_FILE_NAME,
+        "isWorking",
+        &SearchOptions::default(),
+        record_default_callback,
+    );
+
+    assert_eq!(SearchStatusCode::Success, result_code);
+    assert_eq!(vec![(4, -1, None)], *DEFAULT_SCOPES.lock().unwrap());
+}
+
+#[test]
+fn test_bad_scope_header_returns_bad_pattern_error_code() {
+    let scope_header = as_cstring("(");
+    let options = SearchOptions {
+        scope_header: scope_header.as_ptr(),
+        ..SearchOptions::default()
+    };
+
+    let result_code = search(
+        HIVE_JAVA_FILE_NAME,
+        "isWorking",
+        &options,
+        record_bad_header_callback,
+    );
+
+    assert_eq!(SearchStatusCode::ErrorBadPattern, result_code);
+}
+
+type Scope = (c_int, c_int, Option<String>);
+
+// each test records into its own list, so tests running concurrently don't interfere
+static JAVA_SCOPES: Mutex<Vec<Scope>> = Mutex::new(Vec::new());
+static CUSTOM_SCOPES: Mutex<Vec<Scope>> = Mutex::new(Vec::new());
+static DEFAULT_SCOPES: Mutex<Vec<Scope>> = Mutex::new(Vec::new());
+
+extern "C" fn record_java_callback(result: SearchResult) -> bool {
+    JAVA_SCOPES.lock().unwrap().push((
+        result.line_number,
+        result.scope_line_number,
+        scope_of(&result),
+    ));
+    true
+}
+
+extern "C" fn record_custom_callback(result: SearchResult) -> bool {
+    CUSTOM_SCOPES.lock().unwrap().push((
+        result.line_number,
+        result.scope_line_number,
+        scope_of(&result),
+    ));
+    true
+}
+
+extern "C" fn record_default_callback(result: SearchResult) -> bool {
+    DEFAULT_SCOPES.lock().unwrap().push((
+        result.line_number,
+        result.scope_line_number,
+        scope_of(&result),
+    ));
+    true
+}
+
+extern "C" fn record_bad_header_callback(_: SearchResult) -> bool {
+    true
+}
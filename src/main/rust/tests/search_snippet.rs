@@ -0,0 +1,103 @@
+use std::os::raw::c_int;
+use std::ptr;
+use std::sync::Mutex;
+
+use super::*;
+
+fn search_hive(snippet: &str, callback: SearchResultCallbackFn) -> SearchStatusCode {
+    let filename = as_cstring(HIVE_JAVA_FILE_NAME);
+    let snippet = as_cstring(snippet);
+    search_path_snippet(
+        filename.as_ptr(),
+        snippet.as_ptr(),
+        ptr::null(),
+        Some(callback),
+    )
+}
+
+#[test]
+fn test_rewrapped_snippet_is_found_with_its_line_range() {
+    let snippet = "return bees.stream()  .filter(Bee::isWorking)\n.count();";
+
+    let result_code = search_hive(snippet, record_rewrapped_callback);
+
+    assert_eq!(SearchStatusCode::Success, result_code);
+    assert_eq!(vec![(3, 5)], *REWRAPPED.lock().unwrap());
+}
+
+#[test]
+fn test_snippet_without_whitespace_does_not_match_whitespace() {
+    // there's a line break between `stream()` and `.filter` in the file, but nothing in the snippet
+    let snippet = "return bees.stream().filter(Bee::isWorking)\n.count();";
+
+    let result_code = search_hive(snippet, record_unwrapped_callback);
+
+    assert_eq!(SearchStatusCode::Success, result_code);
+    assert!(UNWRAPPED.lock().unwrap().is_empty());
+}
+
+#[test]
+fn test_reindented_snippet_is_found() {
+    let snippet = "\tpublic long countWorkers(List<Bee> bees) {\n\t\treturn";
+    let result_code = search_hive(snippet, record_reindented_callback);
+
+    assert_eq!(SearchStatusCode::Success, result_code);
+    assert_eq!(vec![(2, 3)], *REINDENTED.lock().unwrap());
+}
+
+#[test]
+fn test_snippet_special_characters_are_literal() {
+    let result_code = search_hive("Bee::isWorking)", record_literal_callback);
+
+    assert_eq!(SearchStatusCode::Success, result_code);
+    assert_eq!(vec![(4, 4)], *LITERAL.lock().unwrap());
+}
+
+#[test]
+fn test_blank_snippet_returns_bad_pattern_error_code() {
+    let result_code = search_hive(" \n\t", record_nothing_callback);
+
+    assert_eq!(SearchStatusCode::ErrorBadPattern, result_code);
+}
+
+// each test records into its own list, so tests running concurrently don't interfere
+static UNWRAPPED: Mutex<Vec<(c_int, c_int)>> = Mutex::new(Vec::new());
+static REWRAPPED: Mutex<Vec<(c_int, c_int)>> = Mutex::new(Vec::new());
+static REINDENTED: Mutex<Vec<(c_int, c_int)>> = Mutex::new(Vec::new());
+static LITERAL: Mutex<Vec<(c_int, c_int)>> = Mutex::new(Vec::new());
+
+extern "C" fn record_unwrapped_callback(result: SearchResult) -> bool {
+    UNWRAPPED
+        .lock()
+        .unwrap()
+        .push((result.line_number, result.end_line_number));
+    true
+}
+
+extern "C" fn record_rewrapped_callback(result: SearchResult) -> bool {
+    REWRAPPED
+        .lock()
+        .unwrap()
+        .push((result.line_number, result.end_line_number));
+    true
+}
+
+extern "C" fn record_reindented_callback(result: SearchResult) -> bool {
+    REINDENTED
+        .lock()
+        .unwrap()
+        .push((result.line_number, result.end_line_number));
+    true
+}
+
+extern "C" fn record_literal_callback(result: SearchResult) -> bool {
+    LITERAL
+        .lock()
+        .unwrap()
+        .push((result.line_number, result.end_line_number));
+    true
+}
+
+extern "C" fn record_nothing_callback(_: SearchResult) -> bool {
+    true
+}
@@ -0,0 +1,90 @@
+use std::os::raw::c_int;
+use std::sync::Mutex;
+
+use super::*;
+
+fn search_bee_movie_as(
+    pattern: &str,
+    syntax: PatternSyntax,
+    callback: SearchResultCallbackFn,
+) -> SearchStatusCode {
+    let options = SearchOptions {
+        syntax: syntax as c_int,
+        ..SearchOptions::default()
+    };
+    search_bee_movie(pattern, &options, callback)
+}
+
+#[test]
+fn test_wildcards_match_whole_lines() {
+    let result_code = search_bee_movie_as(
+        "Is that * gel?",
+        PatternSyntax::Wildcard,
+        record_wildcard_callback,
+    );
+
+    assert_eq!(SearchStatusCode::Success, result_code);
+    assert_eq!(vec![12], *WILDCARD.lock().unwrap());
+}
+
+#[test]
+fn test_sql_like_matches_whole_lines() {
+    let result_code = search_bee_movie_as(
+        "%Special day, _raduation%",
+        PatternSyntax::SqlLike,
+        record_like_callback,
+    );
+
+    assert_eq!(SearchStatusCode::Success, result_code);
+    assert_eq!(vec![13], *LIKE.lock().unwrap());
+}
+
+#[test]
+fn test_posix_basic_regex_treats_question_mark_literally() {
+    let result_code = search_bee_movie_as(
+        r"\(fuzz\) gel?$",
+        PatternSyntax::PosixBasic,
+        record_basic_callback,
+    );
+
+    assert_eq!(SearchStatusCode::Success, result_code);
+    assert_eq!(vec![12], *BASIC.lock().unwrap());
+}
+
+#[test]
+fn test_posix_extended_regex_with_named_classes() {
+    let result_code = search_bee_movie_as(
+        "Special[[:space:]]+[[:alpha:]]+, gradua(tion){1}",
+        PatternSyntax::PosixExtended,
+        record_extended_callback,
+    );
+
+    assert_eq!(SearchStatusCode::Success, result_code);
+    assert_eq!(vec![13], *EXTENDED.lock().unwrap());
+}
+
+// each test records into its own list, so tests running concurrently don't interfere
+static WILDCARD: Mutex<Vec<c_int>> = Mutex::new(Vec::new());
+static LIKE: Mutex<Vec<c_int>> = Mutex::new(Vec::new());
+static BASIC: Mutex<Vec<c_int>> = Mutex::new(Vec::new());
+static EXTENDED: Mutex<Vec<c_int>> = Mutex::new(Vec::new());
+
+extern "C" fn record_wildcard_callback(result: SearchResult) -> bool {
+    WILDCARD.lock().unwrap().push(result.line_number);
+    true
+}
+
+extern "C" fn record_like_callback(result: SearchResult) -> bool {
+    LIKE.lock().unwrap().push(result.line_number);
+    true
+}
+
+extern "C" fn record_basic_callback(result: SearchResult) -> bool {
+    BASIC.lock().unwrap().push(result.line_number);
+    true
+}
+
+extern "C" fn record_extended_callback(result: SearchResult) -> bool {
+    EXTENDED.lock().unwrap().push(result.line_number);
+    true
+}
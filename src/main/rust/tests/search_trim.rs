@@ -0,0 +1,190 @@
+use std::os::raw::c_int;
+use std::sync::Mutex;
+
+use super::*;
+
+fn capture_spans_of(result: &SearchResult) -> Vec<(c_int, c_int)> {
+    if result.captures.is_null() {
+        return Vec::new();
+    }
+    let groups =
+        unsafe { std::slice::from_raw_parts(result.captures, result.num_captures as usize) };
+    groups
+        .iter()
+        .map(|group| (group.start, group.end))
+        .collect()
+}
+
+fn trimmed() -> SearchOptions {
+    SearchOptions {
+        strip_line_terminator: 1,
+        trim: 1,
+        ..SearchOptions::default()
+    }
+}
+
+#[test]
+fn test_trimmed_line_has_no_indentation_or_terminator() {
+    let options = SearchOptions {
+        report_match_spans: 1,
+        ..trimmed()
+    };
+
+    let result_code = search(
+        HIVE_JAVA_FILE_NAME,
+        "isWorking",
+        &options,
+        record_trimmed_callback,
+    );
+
+    assert_eq!(SearchStatusCode::Success, result_code);
+    assert_eq!(
+        vec![(".filter(Bee::isWorking)".to_string(), 19, vec![(13, 22)])],
+        *TRIMMED.lock().unwrap(),
+        "The match span should be relative to the trimmed text, which starts 19 bytes into the line"
+    );
+}
+
+#[test]
+fn test_captures_are_relative_to_trimmed_line() {
+    let options = SearchOptions {
+        report_captures: 1,
+        ..trimmed()
+    };
+
+    let result_code = search(
+        HIVE_JAVA_FILE_NAME,
+        r"Bee::(\w+)",
+        &options,
+        record_captures_callback,
+    );
+
+    assert_eq!(SearchStatusCode::Success, result_code);
+    assert_eq!(vec![vec![(8, 22), (13, 22)]], *CAPTURES.lock().unwrap());
+}
+
+#[test]
+fn test_context_lines_are_trimmed_too() {
+    let options = SearchOptions {
+        before_context: 1,
+        ..trimmed()
+    };
+
+    let result_code = search(
+        HIVE_JAVA_FILE_NAME,
+        "isWorking",
+        &options,
+        record_context_callback,
+    );
+
+    assert_eq!(SearchStatusCode::Success, result_code);
+    assert_eq!(
+        vec![
+            (
+                SearchResultKind::BeforeContext,
+                "return bees.stream()".to_string()
+            ),
+            (
+                SearchResultKind::Match,
+                ".filter(Bee::isWorking)".to_string()
+            ),
+        ],
+        *CONTEXT.lock().unwrap()
+    );
+}
+
+#[test]
+fn test_stripping_terminator_removes_carriage_return_too() {
+    let options = SearchOptions {
+        strip_line_terminator: 1,
+        ..SearchOptions::default()
+    };
+
+    let result_code = search(CRLF_FILE_NAME, "second", &options, record_crlf_callback);
+
+    assert_eq!(SearchStatusCode::Success, result_code);
+    assert_eq!(vec!["second line".to_string()], *CRLF.lock().unwrap());
+}
+
+#[test]
+fn test_stripping_terminator_in_crlf_mode() {
+    let options = SearchOptions {
+        line_terminator: LineTerminatorKind::Crlf as c_int,
+        strip_line_terminator: 1,
+        ..SearchOptions::default()
+    };
+
+    let result_code = search(CRLF_FILE_NAME, "line$", &options, record_crlf_mode_callback);
+
+    assert_eq!(SearchStatusCode::Success, result_code);
+    assert_eq!(
+        vec![
+            "first line".to_string(),
+            "second line".to_string(),
+            "third line".to_string(),
+        ],
+        *CRLF_MODE.lock().unwrap()
+    );
+}
+
+#[test]
+fn test_lines_keep_their_terminator_by_default() {
+    let result_code = search(
+        CRLF_FILE_NAME,
+        "third",
+        &SearchOptions::default(),
+        record_untrimmed_callback,
+    );
+
+    assert_eq!(SearchStatusCode::Success, result_code);
+    assert_eq!(
+        vec!["third line\r\n".to_string()],
+        *UNTRIMMED.lock().unwrap()
+    );
+}
+
+type Trimmed = (String, i64, Vec<(c_int, c_int)>);
+
+// each test records into its own list, so tests running concurrently don't interfere
+static TRIMMED: Mutex<Vec<Trimmed>> = Mutex::new(Vec::new());
+static CAPTURES: Mutex<Vec<Vec<(c_int, c_int)>>> = Mutex::new(Vec::new());
+static CONTEXT: Mutex<Vec<(SearchResultKind, String)>> = Mutex::new(Vec::new());
+static CRLF: Mutex<Vec<String>> = Mutex::new(Vec::new());
+static CRLF_MODE: Mutex<Vec<String>> = Mutex::new(Vec::new());
+static UNTRIMMED: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+extern "C" fn record_trimmed_callback(result: SearchResult) -> bool {
+    TRIMMED
+        .lock()
+        .unwrap()
+        .push((text_of(&result), result.line_offset, spans_of(&result)));
+    true
+}
+
+extern "C" fn record_captures_callback(result: SearchResult) -> bool {
+    CAPTURES.lock().unwrap().push(capture_spans_of(&result));
+    true
+}
+
+extern "C" fn record_context_callback(result: SearchResult) -> bool {
+    CONTEXT
+        .lock()
+        .unwrap()
+        .push((result.kind, text_of(&result)));
+    true
+}
+
+extern "C" fn record_crlf_callback(result: SearchResult) -> bool {
+    CRLF.lock().unwrap().push(text_of(&result));
+    true
+}
+
+extern "C" fn record_crlf_mode_callback(result: SearchResult) -> bool {
+    CRLF_MODE.lock().unwrap().push(text_of(&result));
+    true
+}
+
+extern "C" fn record_untrimmed_callback(result: SearchResult) -> bool {
+    UNTRIMMED.lock().unwrap().push(text_of(&result));
+    true
+}
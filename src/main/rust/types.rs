@@ -1,21 +1,125 @@
 // Defines the various types and enums used by this wrapper library
+use std::cell::Cell;
+use std::convert::TryFrom;
+use std::ffi::CString;
 use std::fmt;
+use std::io;
+use std::ops::Range;
 use std::os::raw::c_char;
 use std::os::raw::c_int;
 use std::path::Path;
+use std::ptr;
 
-use grep::searcher::{Searcher, Sink, SinkError, SinkMatch};
+use grep::matcher::{Captures, Matcher};
+use grep::regex::RegexMatcher;
+use grep::searcher::{
+    Searcher, Sink, SinkContext, SinkContextKind, SinkError, SinkFinish, SinkMatch,
+};
+
+use crate::columns::{column_at, preview_window};
+use crate::fuzzy::FuzzyMatcher;
+use crate::literals::LiteralSetMatcher;
+use crate::normalize::{Normalization, NormalizingMatcher};
+#[cfg(feature = "pcre2")]
+use crate::pcre2::Pcre2Matcher;
+use crate::scope::{ScopeHeader, ScopeHeaders, ScopeTracker};
 
 // For use returning back through the FFI.
 // Note that the bytes inside are NOT nul-terminated!
 #[repr(C)]
-#[no_mangle] // or else JNA can't determine what fields the struct has
 pub struct SearchResult {
     pub file_name: *const c_char,
     pub line_number: c_int,
     pub bytes: *const u8,
     // NOT nul-terminated!
     pub num_bytes: c_int,
+    // which entry of a literal dictionary matched first on this line, or -1 for a regex search
+    pub pattern_index: c_int,
+    // the last line of the match; the same as `line_number` unless searching in multi-line mode
+    pub end_line_number: c_int,
+    // why this line was reported
+    pub kind: SearchResultKind,
+    // every capture group of every match on the line, when `report_captures` is set.
+    // Like `bytes`, this array is only valid until the callback returns.
+    pub captures: *const CaptureGroup,
+    pub num_captures: c_int,
+    // where every match is within the line, when `report_match_spans` is set.
+    // Like `bytes`, this array is only valid until the callback returns.
+    pub match_spans: *const MatchSpan,
+    pub num_match_spans: c_int,
+    // the 1-based column of the first match, like `rg --column`, counted in the search's `column_unit`.
+    // In multi-line mode, this is the column within the line where the match starts.
+    // -1 if the result has no match, such as an inverted result.
+    pub column: c_int,
+    // where `bytes` starts in the file, like `rg --byte-offset`
+    pub byte_offset: i64,
+    // how many edits away from the pattern the closest match is, for an approximate search; otherwise -1
+    pub edit_distance: c_int,
+    // the nearest scope header line before the match, like the method it's in, when the options ask for one.
+    // The header's bytes don't include its line terminator, and are only valid until the callback returns.
+    // The line number is -1 and the bytes are null if there's no header before the match.
+    pub scope_line_number: c_int,
+    pub scope_bytes: *const u8,
+    pub scope_num_bytes: c_int,
+    // for a `Count` result, how many lines matched, like `rg --count`; otherwise -1
+    pub num_matching_lines: i64,
+    // for a `Count` result from `CountMode::Matches`, how many matches there were, like `rg --count-matches`.
+    // Otherwise -1.
+    pub num_matches: i64,
+    // for a `Count` or `FileEnd` result, 1 if the search of the file stopped at `max_count`
+    // while there were still more matching lines in it; otherwise 0
+    pub truncated: c_int,
+    // where `bytes` starts within its line, which is 0 unless the result is part of a line.
+    // -1 for a result that isn't a line at all.
+    pub line_offset: i64,
+    // 1 if `bytes` is a preview cut from a line longer than `max_columns`; otherwise 0
+    pub line_truncated: c_int,
+}
+
+// Byte offsets of one match, relative to the start of its SearchResult's bytes
+#[repr(C)]
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub struct MatchSpan {
+    pub start: c_int,
+    // exclusive
+    pub end: c_int,
+}
+
+// One capture group from one match, pointing into the same buffer as its SearchResult's bytes
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct CaptureGroup {
+    // which match on the line this group belongs to, counting from 0
+    pub match_index: c_int,
+    // the group's number in the pattern; group 0 is the whole match
+    pub group_index: c_int,
+    // the nul-terminated name of the group, or null for an unnamed group
+    pub name: *const c_char,
+    // offsets relative to the start of the result's bytes, or -1 if the group didn't participate
+    pub start: c_int,
+    pub end: c_int,
+    // NOT nul-terminated, and null if the group didn't participate
+    pub bytes: *const u8,
+    pub num_bytes: c_int,
+}
+
+#[repr(C)]
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub enum SearchResultKind {
+    // the line matched the search pattern
+    Match = 0,
+    // the line did NOT match the search pattern, and was reported because the match was inverted
+    InvertedMatch = 1,
+    // a line before a match, reported because of `before_context`
+    BeforeContext = 2,
+    // a line after a match, reported because of `after_context`
+    AfterContext = 3,
+    // not a line at all: the gap between two runs of matches and context, like the `--` from `rg -C`
+    ContextBreak = 4,
+    // not a line at all: the counts for a whole file, from a search with a `count` mode
+    Count = 5,
+    // not a line at all: the end of a file, with its counts, when `report_file_end` is set
+    FileEnd = 6,
 }
 
 #[repr(C)]
@@ -26,12 +130,461 @@ pub enum SearchStatusCode {
     MissingFilename = 1,
     MissingSearchText = 2,
     MissingCallback = 3,
+    // an enum or flag in the `SearchOptions` has a value it can't take
+    InvalidOptions = 4,
     // Failure from inside ripgrep:
     ErrorBadPattern = 11,
     ErrorCouldNotOpenFile = 12,
     ErrorFromRipgrep = 13,
+    ErrorEngineUnavailable = 14,
+    ErrorPatternTooBig = 15,
     // Failure from inside the callback:
     ErrorFromCallback = 21,
+    // Not a failure: the search stopped at `max_results`, and there were more results to report
+    SuccessTruncated = 31,
+    // Not a failure: `search_path_exists` didn't find a match anywhere
+    NotFound = 32,
+}
+
+// Converts the `c_int` that Java passes for an enum, rejecting any value that isn't one of its variants
+macro_rules! enum_from_c_int {
+    ($name:ident { $($variant:ident),* $(,)? }) => {
+        impl TryFrom<c_int> for $name {
+            type Error = SearchStatusCode;
+
+            fn try_from(value: c_int) -> Result<Self, SearchStatusCode> {
+                $(
+                    if value == $name::$variant as c_int {
+                        return Ok($name::$variant);
+                    }
+                )*
+                Err(SearchStatusCode::InvalidOptions)
+            }
+        }
+    };
+}
+
+// Which regex engine compiles the search text
+#[repr(C)]
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub enum RegexEngine {
+    // Rust's regex engine, which guarantees linear-time searches
+    Default = 0,
+    // PCRE2, which supports look-around and backreferences like java.util.regex.
+    // Only available when this library is built with the `pcre2` feature.
+    Pcre2 = 1,
+    // Rust's regex engine, falling back to PCRE2 only when the pattern is rejected
+    Auto = 2,
+}
+
+// What ends a line (or record) in the files being searched
+#[repr(C)]
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub enum LineTerminatorKind {
+    // `\n`, which also works for most CRLF files as long as `$` isn't used
+    Lf = 0,
+    // `\r\n`, so `$` matches before the `\r`
+    Crlf = 1,
+    // any single byte, such as `\0` for NUL-separated records like `rg --null-data`
+    Byte = 2,
+}
+
+// What a column number counts
+#[repr(C)]
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub enum ColumnUnit {
+    // bytes of UTF-8, which is what ripgrep reports
+    Byte = 0,
+    // characters (Unicode scalar values)
+    Char = 1,
+    // UTF-16 code units, which is how Java indexes a String
+    Utf16 = 2,
+    // extended grapheme clusters, which is closest to what a user sees as one character
+    Grapheme = 3,
+}
+
+// Which Unicode normalization form text is put into before searching, so equivalent text matches
+#[repr(C)]
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub enum NormalizationForm {
+    // search the text exactly as it is
+    None = 0,
+    // canonical composition, so "e" followed by a combining accent matches "é"
+    Nfc = 1,
+    // compatibility composition, which also matches ligatures like "ﬁ" with "fi", and "²" with "2"
+    Nfkc = 2,
+}
+
+// Which dialect the search text is written in
+#[repr(C)]
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub enum PatternSyntax {
+    // the regex engine's own syntax
+    Regex = 0,
+    // shell-style `*` and `?` wildcards, matching a whole line
+    Wildcard = 1,
+    // SQL `LIKE` with `%` and `_`, matching a whole line
+    SqlLike = 2,
+    // POSIX basic regexes, like `grep` uses by default
+    PosixBasic = 3,
+    // POSIX extended regexes, like `grep -E`
+    PosixExtended = 4,
+}
+
+// Reports each match along with the whole block of lines around it, instead of just the matching lines
+#[repr(C)]
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub enum BlockContext {
+    // report matching lines on their own
+    None = 0,
+    // paragraphs separated by blank lines
+    Paragraph = 1,
+    // records which each start with a line matching `record_start`, like the timestamp on a log entry
+    Record = 2,
+}
+
+// Languages with built-in scope headers, for finding the method or class around each match
+#[repr(C)]
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub enum ScopeLanguage {
+    None = 0,
+    // classes, interfaces, enums, records and methods
+    Java = 1,
+    // functions, types, traits, impl blocks and modules
+    Rust = 2,
+    // functions and classes
+    Python = 3,
+    // functions and types defined at the start of a line
+    C = 4,
+}
+
+// Reports how much of each file matched, instead of the matches themselves
+#[repr(C)]
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub enum CountMode {
+    // report every matching line
+    None = 0,
+    // count the matching lines in each file, like `rg --count`
+    Lines = 1,
+    // count the matching lines and every match on them, like `rg --count-matches`
+    Matches = 2,
+}
+
+// Which files `search_path_files` lists
+#[repr(C)]
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub enum FileListMode {
+    // files with at least one match, like `rg --files-with-matches`
+    WithMatches = 0,
+    // files without any matches, like `rg --files-without-match`
+    WithoutMatch = 1,
+}
+
+enum_from_c_int!(RegexEngine {
+    Default,
+    Pcre2,
+    Auto
+});
+enum_from_c_int!(LineTerminatorKind { Lf, Crlf, Byte });
+enum_from_c_int!(ColumnUnit {
+    Byte,
+    Char,
+    Utf16,
+    Grapheme
+});
+enum_from_c_int!(NormalizationForm { None, Nfc, Nfkc });
+enum_from_c_int!(PatternSyntax {
+    Regex,
+    Wildcard,
+    SqlLike,
+    PosixBasic,
+    PosixExtended
+});
+enum_from_c_int!(BlockContext {
+    None,
+    Paragraph,
+    Record
+});
+enum_from_c_int!(ScopeLanguage {
+    None,
+    Java,
+    Rust,
+    Python,
+    C
+});
+enum_from_c_int!(CountMode {
+    None,
+    Lines,
+    Matches
+});
+enum_from_c_int!(FileListMode {
+    WithMatches,
+    WithoutMatch
+});
+
+// Settings for a single search, passed by reference from Java.
+// A null pointer means every setting takes its default value.
+// Every default is zero, so a zero-initialized struct from Java also searches with the defaults.
+// Each enum is passed as its `c_int` value, and each flag as a `c_int` of 0 or 1, like JNA passes a Java boolean.
+// Anything else makes the search return `InvalidOptions`.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct SearchOptions {
+    // a `RegexEngine`
+    pub engine: c_int,
+    // lets a match span several lines, which are then reported together as one result
+    pub multi_line: c_int,
+    // lets `.` match line terminators; only useful in multi-line mode
+    pub dot_matches_new_line: c_int,
+    // reports the lines which do NOT match instead, like `rg --invert-match`
+    pub invert_match: c_int,
+    // a `LineTerminatorKind`
+    pub line_terminator: c_int,
+    // the byte ending each line, only used when `line_terminator` is `Byte`
+    pub line_terminator_byte: u8,
+    // The following limits guard against patterns like `\w{1000}` that are costly to compile.
+    // They only apply to the default regex engine, and 0 means the engine's own default.
+    // The most memory, in bytes, a single compiled regex may use:
+    pub regex_size_limit: u64,
+    // The most memory, in bytes, the lazy DFA may use to speed up a search:
+    pub dfa_size_limit: u64,
+    // The deepest nesting of groups and repetitions allowed while parsing a pattern:
+    pub nest_limit: u32,
+    // makes classes like `\w` and `.` ASCII-only with either engine, which is faster to compile and search
+    pub disable_unicode: c_int,
+    // reports the span of every capture group of every match, along with the group names
+    pub report_captures: c_int,
+    // reports where every match is within each line, for highlighting
+    pub report_match_spans: c_int,
+    // a `ColumnUnit`
+    pub column_unit: c_int,
+    // expands tabs to the next multiple of this many columns, or 0 to count a tab as one column
+    pub tab_width: u32,
+    // reports each match as its own result, holding just the matched bytes, like `rg --only-matching`
+    pub only_matching: c_int,
+    // A `NormalizationForm` for both the pattern and the text being searched.
+    // Offsets are still reported in the original text.
+    pub normalization: c_int,
+    // also strips accents and other diacritics, so "resume" finds "résumé"; implies at least NFC
+    pub fold_diacritics: c_int,
+    // the `PatternSyntax` of the search text, which is translated before it's compiled by either regex engine
+    pub syntax: c_int,
+    // how many lines to report before and after each match, like `rg -B` and `rg -A`
+    pub before_context: u32,
+    pub after_context: u32,
+    // A `BlockContext`, to report every block containing a match as one result, with its whole line range.
+    // This takes the place of `before_context` and `after_context`.
+    pub block_context: c_int,
+    // the nul-terminated regex for the first line of each record, when `block_context` is `Record`
+    pub record_start: *const c_char,
+    // a `ScopeLanguage`, to report the nearest line before each match which starts a scope, like `git grep --show-function`
+    pub scope_language: c_int,
+    // the nul-terminated regex for a scope header line, which takes the place of `scope_language`'s headers
+    pub scope_header: *const c_char,
+    // A `CountMode`, to report one `Count` result for each file with any matching lines, and no lines at all.
+    // In an inverted search, each line that doesn't match counts as one match.
+    pub count: c_int,
+    // Stops searching each file after this many matching lines, like `rg --max-count`.
+    // Any after-context of the last one is still reported, even lines which match too. 0 means there's no limit.
+    pub max_count: u64,
+    // reports a `FileEnd` result after searching each file, with how many lines matched and whether it was truncated
+    pub report_file_end: c_int,
+    // Stops the whole search after this many `Match`, `InvertedMatch` or `Count` results, across every file.
+    // If there were any more, the search returns `SuccessTruncated`. 0 means there's no limit.
+    pub max_results: u64,
+    // Replaces results longer than this many bytes, not counting the line terminator,
+    // with a preview of that many bytes around the first match, like `rg --max-columns-preview`.
+    // Captures and match spans outside the preview are left out. 0 means no limit.
+    pub max_columns: u32,
+    // gives an over-long line a preview around each of its matches, rather than just the first
+    pub preview_each_match: c_int,
+    // Leaves the line terminator out of each line's bytes, along with the carriage return of a CRLF.
+    // Match spans and captures are relative to the bytes that are left, like always.
+    pub strip_line_terminator: c_int,
+    // leaves the leading whitespace out of each line's bytes, like `rg --trim`
+    pub trim: c_int,
+}
+
+impl Default for SearchOptions {
+    fn default() -> Self {
+        SearchOptions {
+            engine: 0,
+            multi_line: 0,
+            dot_matches_new_line: 0,
+            invert_match: 0,
+            line_terminator: 0,
+            line_terminator_byte: 0,
+            regex_size_limit: 0,
+            dfa_size_limit: 0,
+            nest_limit: 0,
+            disable_unicode: 0,
+            report_captures: 0,
+            report_match_spans: 0,
+            column_unit: 0,
+            tab_width: 0,
+            only_matching: 0,
+            normalization: 0,
+            fold_diacritics: 0,
+            syntax: 0,
+            before_context: 0,
+            after_context: 0,
+            block_context: 0,
+            record_start: ptr::null(),
+            scope_language: 0,
+            scope_header: ptr::null(),
+            count: 0,
+            max_count: 0,
+            report_file_end: 0,
+            max_results: 0,
+            max_columns: 0,
+            preview_each_match: 0,
+            strip_line_terminator: 0,
+            trim: 0,
+        }
+    }
+}
+
+// The settings of `SearchOptions`, once each enum and flag has been checked and converted.
+// Each setting is described on `SearchOptions`.
+#[derive(Debug, Clone, Copy)]
+pub struct ParsedOptions {
+    pub engine: RegexEngine,
+    pub multi_line: bool,
+    pub dot_matches_new_line: bool,
+    pub invert_match: bool,
+    pub line_terminator: LineTerminatorKind,
+    pub line_terminator_byte: u8,
+    pub regex_size_limit: u64,
+    pub dfa_size_limit: u64,
+    pub nest_limit: u32,
+    pub disable_unicode: bool,
+    pub report_captures: bool,
+    pub report_match_spans: bool,
+    pub column_unit: ColumnUnit,
+    pub tab_width: u32,
+    pub only_matching: bool,
+    pub normalization: NormalizationForm,
+    pub fold_diacritics: bool,
+    pub syntax: PatternSyntax,
+    pub before_context: u32,
+    pub after_context: u32,
+    pub block_context: BlockContext,
+    pub record_start: *const c_char,
+    pub scope_language: ScopeLanguage,
+    pub scope_header: *const c_char,
+    pub count: CountMode,
+    pub max_count: u64,
+    pub report_file_end: bool,
+    pub max_results: u64,
+    pub max_columns: u32,
+    pub preview_each_match: bool,
+    pub strip_line_terminator: bool,
+    pub trim: bool,
+}
+
+impl Default for ParsedOptions {
+    fn default() -> Self {
+        ParsedOptions {
+            engine: RegexEngine::Default,
+            multi_line: false,
+            dot_matches_new_line: false,
+            invert_match: false,
+            line_terminator: LineTerminatorKind::Lf,
+            line_terminator_byte: 0,
+            regex_size_limit: 0,
+            dfa_size_limit: 0,
+            nest_limit: 0,
+            disable_unicode: false,
+            report_captures: false,
+            report_match_spans: false,
+            column_unit: ColumnUnit::Byte,
+            tab_width: 0,
+            only_matching: false,
+            normalization: NormalizationForm::None,
+            fold_diacritics: false,
+            syntax: PatternSyntax::Regex,
+            before_context: 0,
+            after_context: 0,
+            block_context: BlockContext::None,
+            record_start: ptr::null(),
+            scope_language: ScopeLanguage::None,
+            scope_header: ptr::null(),
+            count: CountMode::None,
+            max_count: 0,
+            report_file_end: false,
+            max_results: 0,
+            max_columns: 0,
+            preview_each_match: false,
+            strip_line_terminator: false,
+            trim: false,
+        }
+    }
+}
+
+impl ParsedOptions {
+    // The byte that ends each line; for CRLF, that's the `\n`
+    pub fn line_terminator_byte(&self) -> u8 {
+        match self.line_terminator {
+            LineTerminatorKind::Lf | LineTerminatorKind::Crlf => b'\n',
+            LineTerminatorKind::Byte => self.line_terminator_byte,
+        }
+    }
+
+    // How text is normalized before searching, if at all
+    pub fn normalizer(&self) -> Option<Normalization> {
+        if self.normalization == NormalizationForm::None && !self.fold_diacritics {
+            return None;
+        }
+        Some(Normalization {
+            form: self.normalization,
+            fold_diacritics: self.fold_diacritics,
+        })
+    }
+}
+
+// A compiled search pattern, from whichever regex engine was chosen.
+// Only one of these is built per search, so the size difference between variants doesn't matter.
+#[allow(clippy::large_enum_variant)]
+#[derive(Clone, Debug)]
+pub enum PatternMatcher {
+    RustRegex(RegexMatcher),
+    #[cfg(feature = "pcre2")]
+    Pcre2(Pcre2Matcher),
+}
+
+// Why a pattern was rejected
+#[repr(C)]
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub enum PatternErrorKind {
+    // the pattern is fine
+    None = 0,
+    // the pattern isn't a valid regex, such as an unclosed group
+    Syntax = 1,
+    // the pattern uses a feature the regex engine doesn't support, such as look-around
+    Unsupported = 2,
+    // the pattern goes over one of the size or nesting limits
+    TooBig = 3,
+    // the pattern could never be matched line by line, such as a literal `\n`
+    NotAllowed = 4,
+}
+
+// Describes what's wrong with a pattern, so Java can point at the offending syntax.
+// Java allocates this, along with the buffer the message is written into.
+#[repr(C)]
+pub struct PatternDiagnostic {
+    pub kind: PatternErrorKind,
+    // where the problem is in the pattern, or -1 when it can't be pinned down
+    pub start_byte: c_int,
+    pub end_byte: c_int,
+    // the same span in UTF-16 code units, like Java's `char` indexes, or -1
+    pub start_char: c_int,
+    pub end_char: c_int,
+    // Java-owned buffer for a human-readable message, which is always nul-terminated.
+    // Messages too long for the buffer are cut short.
+    pub message: *mut c_char,
+    pub message_capacity: c_int,
+    // the full length of the message in bytes, which may be more than fit in the buffer
+    pub message_length: c_int,
 }
 
 // indicates Success on true, Failure on false
@@ -40,50 +593,990 @@ pub type SearchResultCallbackFn = extern "C" fn(SearchResult) -> bool;
 // #[cfg(windows)]
 // pub type SearchResultCallbackFn = extern "stdcall" fn(SearchResult) -> bool;
 
-#[derive(Clone)]
-pub struct SearchResultCallbackSink<'a>(pub SearchResultCallbackFn, pub &'a Path);
+// Called with the nul-terminated path of each file listed by `search_path_files`.
+// Like a result's bytes, the path is only valid until the callback returns.
+// indicates Success on true, Failure on false
+pub type FilePathCallbackFn = extern "C" fn(*const c_char) -> bool;
+
+// Anything we can search with, along with any extra details it can report about a matched line
+pub trait ResultMatcher: Matcher {
+    // The index of the pattern which matched the given line, for matchers that search for several patterns
+    fn pattern_index(&self, _line: &[u8]) -> Option<usize> {
+        None
+    }
 
-pub struct CallbackError {
-    error_message: String,
+    // The fewest edits between the pattern and the given bytes, for matchers that allow approximate matches
+    fn edit_distance(&self, _bytes: &[u8]) -> Option<usize> {
+        None
+    }
+
+    // Runs `search` over the whole haystack, for matchers that can get it ready to be searched part by part
+    fn with_haystack<T>(&self, _haystack: &[u8], search: impl FnOnce() -> T) -> T {
+        search()
+    }
 }
 
-impl SinkError for CallbackError {
-    fn error_message<T: fmt::Display>(message: T) -> Self {
-        Self {
-            error_message: format!("{}", message),
+impl ResultMatcher for RegexMatcher {}
+
+#[cfg(feature = "pcre2")]
+impl ResultMatcher for Pcre2Matcher {
+    fn with_haystack<T>(&self, haystack: &[u8], search: impl FnOnce() -> T) -> T {
+        self.with_prepared(haystack, search)
+    }
+}
+
+impl ResultMatcher for LiteralSetMatcher {
+    fn pattern_index(&self, line: &[u8]) -> Option<usize> {
+        self.find_literal(line).map(|(index, _)| index)
+    }
+}
+
+impl<M: ResultMatcher> ResultMatcher for NormalizingMatcher<M> {
+    fn pattern_index(&self, line: &[u8]) -> Option<usize> {
+        self.inner().pattern_index(&self.normalize(line).text)
+    }
+
+    fn edit_distance(&self, bytes: &[u8]) -> Option<usize> {
+        self.inner().edit_distance(&self.normalize(bytes).text)
+    }
+
+    fn with_haystack<T>(&self, haystack: &[u8], search: impl FnOnce() -> T) -> T {
+        self.with_prepared(haystack, |normalized| {
+            self.inner().with_haystack(normalized, search)
+        })
+    }
+}
+
+impl ResultMatcher for FuzzyMatcher {
+    fn edit_distance(&self, bytes: &[u8]) -> Option<usize> {
+        self.best_distance(bytes)
+    }
+}
+
+// Everything a sink needs which stays the same for every file searched
+pub struct SearchContext<'a, M> {
+    pub callback: SearchResultCallbackFn,
+    pub matcher: &'a M,
+    pub options: &'a ParsedOptions,
+    // the nul-terminated names of the pattern's capture groups, by group index
+    pub capture_names: Vec<Option<CString>>,
+    // the line that starts each record, for block context; blank lines separate blocks without it
+    pub record_start: Option<RegexMatcher>,
+    // the lines which start a scope, when each match is reported with the scope it's in
+    pub scope_headers: Option<ScopeHeaders>,
+    // how many results have counted towards `max_results` so far, in every file searched
+    pub num_results: Cell<u64>,
+    // whether a result went over `max_results`, which ends the search
+    pub truncated: Cell<bool>,
+}
+
+impl<'a, M: ResultMatcher> SearchContext<'a, M> {
+    pub fn new(
+        callback: SearchResultCallbackFn,
+        matcher: &'a M,
+        options: &'a ParsedOptions,
+        capture_names: Vec<Option<CString>>,
+        record_start: Option<RegexMatcher>,
+        scope_headers: Option<ScopeHeaders>,
+    ) -> Self {
+        SearchContext {
+            callback,
+            matcher,
+            options,
+            capture_names,
+            record_start,
+            scope_headers,
+            num_results: Cell::new(0),
+            truncated: Cell::new(false),
+        }
+    }
+
+    fn capture_name(&self, group_index: usize) -> *const c_char {
+        match self.capture_names.get(group_index) {
+            Some(Some(name)) => name.as_ptr(),
+            _ => ptr::null(),
         }
     }
 }
 
-impl Sink for SearchResultCallbackSink<'_> {
-    type Error = CallbackError;
+pub struct SearchResultCallbackSink<'a, M> {
+    context: &'a SearchContext<'a, M>,
+    path: &'a Path,
+    block: Block,
+    // reads through the whole file for scope headers, when the options ask for them
+    scope: Option<ScopeTracker<'a>>,
+    // how many times the searcher has found a match in the file, for `max_count`
+    num_matched: u64,
+    // how many more lines the after-context of the last match counted for `max_count` can take
+    after_context_left: u64,
+    // whether there was a match past `max_count`, so searching stopped before the end of the file
+    truncated: bool,
+    // how much of the file has matched so far; the matches are only counted for `CountMode::Matches`
+    matching_lines: u64,
+    matches: u64,
+}
 
-    fn matched(
+impl<'a, M: ResultMatcher> SearchResultCallbackSink<'a, M> {
+    pub fn new(context: &'a SearchContext<'a, M>, path: &'a Path) -> Self {
+        SearchResultCallbackSink {
+            context,
+            path,
+            block: Block::default(),
+            scope: None,
+            num_matched: 0,
+            after_context_left: 0,
+            truncated: false,
+            matching_lines: 0,
+            matches: 0,
+        }
+    }
+
+    // A sink which also reports the scope of each match, from the whole file being searched
+    pub fn with_scope(
+        context: &'a SearchContext<'a, M>,
+        path: &'a Path,
+        haystack: &'a [u8],
+    ) -> Self {
+        let terminator = context.options.line_terminator_byte();
+        SearchResultCallbackSink {
+            scope: Some(ScopeTracker::new(haystack, terminator)),
+            ..SearchResultCallbackSink::new(context, path)
+        }
+    }
+
+    // The bytes of matched lines the way the searcher matched them, without the terminator of the last one.
+    // Searching them again with the terminator still on could stop `$` from matching at the end of a line.
+    // In multi-line mode, the searcher matched across line terminators, so they're all kept.
+    fn searched<'b>(&self, bytes: &'b [u8]) -> &'b [u8] {
+        let options = self.context.options;
+        if options.multi_line {
+            return bytes;
+        }
+        let byte = [options.line_terminator_byte()];
+        let terminator: &[u8] = match options.line_terminator {
+            LineTerminatorKind::Crlf => b"\r\n",
+            _ => &byte,
+        };
+        bytes.strip_suffix(terminator).unwrap_or(bytes)
+    }
+
+    // Finds every capture group of every match in the bytes of a matched line
+    fn capture_groups(&self, bytes: &[u8]) -> Result<Vec<CaptureGroup>, CallbackError> {
+        let matcher = self.context.matcher;
+        let bytes = self.searched(bytes);
+        let mut captures = matcher
+            .new_captures()
+            .map_err(CallbackError::error_message)?;
+
+        let mut groups = Vec::new();
+        let mut match_index = 0;
+        matcher
+            .captures_iter(bytes, &mut captures, |captures| {
+                for group_index in 0..captures.len() {
+                    let span = captures.get(group_index);
+                    groups.push(CaptureGroup {
+                        match_index,
+                        group_index: group_index as c_int,
+                        name: self.context.capture_name(group_index),
+                        start: span.map(|m| m.start() as c_int).unwrap_or(-1),
+                        end: span.map(|m| m.end() as c_int).unwrap_or(-1),
+                        bytes: span
+                            .map(|m| bytes[m.start()..].as_ptr())
+                            .unwrap_or(ptr::null()),
+                        num_bytes: span.map(|m| m.len() as c_int).unwrap_or(0),
+                    });
+                }
+                match_index += 1;
+                true
+            })
+            .map_err(CallbackError::error_message)?;
+        Ok(groups)
+    }
+
+    // Finds every match in the bytes of a matched line
+    fn match_spans(&self, bytes: &[u8]) -> Result<Vec<MatchSpan>, CallbackError> {
+        let mut spans = Vec::new();
+        self.context
+            .matcher
+            .find_iter(self.searched(bytes), |m| {
+                spans.push(MatchSpan {
+                    start: m.start() as c_int,
+                    end: m.end() as c_int,
+                });
+                true
+            })
+            .map_err(CallbackError::error_message)?;
+        Ok(spans)
+    }
+
+    // Reports every match in the bytes of a matched line as its own result, like `rg --only-matching`
+    fn report_each_match(
         &mut self,
-        _searcher: &Searcher,
-        matched: &SinkMatch,
+        searcher: &Searcher,
+        lines: &Lines,
     ) -> Result<bool, CallbackError> {
+        let options = self.context.options;
+        let bytes = lines.bytes;
+        let captures = if options.report_captures {
+            self.capture_groups(bytes)?
+        } else {
+            Vec::new()
+        };
+
+        for (match_index, span) in self.match_spans(bytes)?.iter().enumerate() {
+            let range = span.start as usize..span.end as usize;
+            // an empty match has nothing to extract
+            if range.is_empty() {
+                continue;
+            }
+            // each match is now a whole result, so its groups are relative to the match instead of the line
+            let rebase = |offset: c_int| if offset < 0 { -1 } else { offset - span.start };
+            let captures = captures
+                .iter()
+                .filter(|group| group.match_index == match_index as c_int)
+                .map(|group| CaptureGroup {
+                    match_index: 0,
+                    start: rebase(group.start),
+                    end: rebase(group.end),
+                    ..*group
+                })
+                .collect();
+            let match_spans = if options.report_match_spans {
+                vec![MatchSpan {
+                    start: 0,
+                    end: range.len() as c_int,
+                }]
+            } else {
+                Vec::new()
+            };
+            let column = Some(self.column_at_offset(bytes, range.start));
+
+            let reported = ReportedSpan {
+                range,
+                captures,
+                match_spans,
+                column,
+                focus: None,
+            };
+            if !self.report(searcher, lines, reported)? {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    // Calls back with one result, made from part or all of the bytes of some matched lines
+    fn report(
+        &mut self,
+        searcher: &Searcher,
+        lines: &Lines,
+        mut reported: ReportedSpan,
+    ) -> Result<bool, CallbackError> {
+        if !self.take_result() {
+            return Ok(false);
+        }
+        let line_bytes = lines.bytes;
+        let range = reported.range.clone();
+        let bytes = &line_bytes[range.clone()];
+        let scope = self.scope_header_before(lines.absolute_byte_offset as usize + range.start);
+        let trimmed = self.trimmed(line_bytes, range.clone());
+        if trimmed != range {
+            reported.clip_to(trimmed.clone());
+        }
+        let line_truncated = self.is_too_long(line_bytes, trimmed.clone());
+        if line_truncated {
+            let focus = match reported.focus.clone() {
+                Some(focus) => Some(focus),
+                None => self
+                    .context
+                    .matcher
+                    .find(self.searched(bytes))
+                    .map_err(CallbackError::error_message)?
+                    .map(|m| range.start + m.start()..range.start + m.end()),
+            };
+            reported.clip_to(self.preview(line_bytes, trimmed, focus));
+        }
+        let window = reported.range.clone();
+        // in multi-line mode, one result can span several lines, and a match can start on any of them
+        let last_byte = if bytes.is_empty() {
+            range.start
+        } else {
+            range.end - 1
+        };
+        let line_number_at = |offset: usize| {
+            lines
+                .line_number
+                .map(|n| (n as usize + self.lines_before(line_bytes, offset)) as c_int)
+                // -1 is a common value to use in Java when an int value is not found
+                .unwrap_or(-1)
+        };
+
         let result = SearchResult {
-            file_name: self.1.to_str().unwrap_or("<unknown file>").as_ptr() as *const i8,
+            line_number: line_number_at(range.start),
+            pattern_index: self
+                .context
+                .matcher
+                .pattern_index(self.searched(bytes))
+                .map(|i| i as c_int)
+                .unwrap_or(-1),
+            end_line_number: line_number_at(last_byte),
+            kind: if searcher.invert_match() {
+                SearchResultKind::InvertedMatch
+            } else {
+                SearchResultKind::Match
+            },
+            captures: if reported.captures.is_empty() {
+                ptr::null()
+            } else {
+                reported.captures.as_ptr()
+            },
+            num_captures: reported.captures.len() as c_int,
+            match_spans: if reported.match_spans.is_empty() {
+                ptr::null()
+            } else {
+                reported.match_spans.as_ptr()
+            },
+            num_match_spans: reported.match_spans.len() as c_int,
+            column: reported.column.map(|c| c as c_int).unwrap_or(-1),
+            edit_distance: self
+                .context
+                .matcher
+                .edit_distance(self.searched(bytes))
+                .map(|d| d as c_int)
+                .unwrap_or(-1),
+            scope_line_number: scope.map(|h| h.line_number as c_int).unwrap_or(-1),
+            scope_bytes: scope.map(|h| h.bytes.as_ptr()).unwrap_or(ptr::null()),
+            scope_num_bytes: scope.map(|h| h.bytes.len() as c_int).unwrap_or(0),
+            line_offset: (window.start - self.line_start(line_bytes, window.start)) as i64,
+            line_truncated: line_truncated as c_int,
+            ..self.plain_result(
+                &line_bytes[window.clone()],
+                lines.absolute_byte_offset + window.start as u64,
+                SearchResultKind::Match,
+            )
+        };
+        self.call_back(result)
+    }
+
+    // Reports matched lines, with whichever details the options ask for
+    fn report_lines(&mut self, searcher: &Searcher, lines: &Lines) -> Result<bool, CallbackError> {
+        let options = self.context.options;
+        let bytes = lines.bytes;
+        // inverted results are lines without any matches, so there's nothing to capture, highlight or extract
+        if searcher.invert_match() {
+            let reported = ReportedSpan {
+                range: 0..bytes.len(),
+                captures: Vec::new(),
+                match_spans: Vec::new(),
+                column: None,
+                focus: None,
+            };
+            return self.report(searcher, lines, reported);
+        }
+        if options.only_matching {
+            return self.report_each_match(searcher, lines);
+        }
+
+        let captures = if options.report_captures {
+            self.capture_groups(bytes)?
+        } else {
+            Vec::new()
+        };
+        let match_spans = if options.report_match_spans {
+            self.match_spans(bytes)?
+        } else {
+            Vec::new()
+        };
+        if options.preview_each_match && self.is_too_long(bytes, 0..bytes.len()) {
+            return self.report_each_preview(searcher, lines, captures, match_spans);
+        }
+        let column = self
+            .context
+            .matcher
+            .find(self.searched(bytes))
+            .map_err(CallbackError::error_message)?
+            .map(|m| self.column_at_offset(bytes, m.start()));
+
+        let reported = ReportedSpan {
+            range: 0..bytes.len(),
+            captures,
+            match_spans,
+            column,
+            focus: None,
+        };
+        self.report(searcher, lines, reported)
+    }
+
+    // Reports a preview of an over-long line around each of its matches, each as its own result
+    fn report_each_preview(
+        &mut self,
+        searcher: &Searcher,
+        lines: &Lines,
+        captures: Vec<CaptureGroup>,
+        match_spans: Vec<MatchSpan>,
+    ) -> Result<bool, CallbackError> {
+        let bytes = lines.bytes;
+        for span in self.match_spans(bytes)? {
+            let focus = span.start as usize..span.end as usize;
+            let reported = ReportedSpan {
+                range: 0..bytes.len(),
+                captures: captures.clone(),
+                match_spans: match_spans.clone(),
+                column: Some(self.column_at_offset(bytes, focus.start)),
+                focus: Some(focus),
+            };
+            if !self.report(searcher, lines, reported)? {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    // Adds the next lines of the file to the block being built up, reporting the last block when a new one starts.
+    // `is_match` says whether the lines matched the pattern, regardless of `invert_match`.
+    fn read_block_lines(
+        &mut self,
+        searcher: &Searcher,
+        lines: Lines,
+        is_match: bool,
+    ) -> Result<bool, CallbackError> {
+        let starts_block = match &self.context.record_start {
+            Some(record_start) => record_start
+                .is_match(self.searched(lines.bytes))
+                .map_err(CallbackError::error_message)?,
+            None => self.is_blank(lines.bytes),
+        };
+        if starts_block && !self.flush_block(searcher)? {
+            return Ok(false);
+        }
+        // blank lines only separate paragraphs, rather than being part of one
+        if self.context.record_start.is_none() && starts_block {
+            return Ok(true);
+        }
+
+        if self.block.bytes.is_empty() {
+            self.block.line_number = lines.line_number;
+            self.block.absolute_byte_offset = lines.absolute_byte_offset;
+        }
+        self.block.bytes.extend_from_slice(lines.bytes);
+        self.block.has_match |= is_match;
+        Ok(true)
+    }
+
+    // Reports the block built up so far, if it's one the search is looking for, and starts a new one
+    fn flush_block(&mut self, searcher: &Searcher) -> Result<bool, CallbackError> {
+        let mut block = std::mem::take(&mut self.block);
+        let wanted = block.has_match != searcher.invert_match();
+        let reported = if block.bytes.is_empty() || !wanted {
+            Ok(true)
+        } else {
+            let lines = Lines {
+                bytes: &block.bytes,
+                line_number: block.line_number,
+                absolute_byte_offset: block.absolute_byte_offset,
+            };
+            self.report_lines(searcher, &lines)
+        };
+        // reuse the buffer for the next block
+        block.bytes.clear();
+        self.block.bytes = block.bytes;
+        reported
+    }
+
+    fn is_blank(&self, line: &[u8]) -> bool {
+        let terminator = self.context.options.line_terminator_byte();
+        line.iter()
+            .all(|&b| b == terminator || b.is_ascii_whitespace())
+    }
+
+    // A result holding some bytes of the file, with none of the details that only apply to matches
+    fn plain_result(&self, bytes: &[u8], byte_offset: u64, kind: SearchResultKind) -> SearchResult {
+        SearchResult {
+            file_name: self.path.to_str().unwrap_or("<unknown file>").as_ptr() as *const i8,
             // -1 is a common value to use in Java when an int value is not found
-            line_number: matched.line_number().map(|n| n as c_int).unwrap_or(-1),
+            line_number: -1,
             // lifetime should be good because the callback will finish before the buffer is modified.
             // callbacks just need to avoid SAVING the byte array passed to it, and should copy from it instead
             // This is easier than allocating a CString and passing it with a nul-terminator,
             // because this way we don't have to free() anything with another FFI call.
             // The drawback is a bit more work on the Java side using this data,
             // and the risk of retaining a dangling pointer to this buffer.
-            bytes: matched.bytes().as_ptr(),
-            num_bytes: matched.bytes().len() as c_int,
+            bytes: bytes.as_ptr(),
+            num_bytes: bytes.len() as c_int,
+            pattern_index: -1,
+            end_line_number: -1,
+            kind,
+            captures: ptr::null(),
+            num_captures: 0,
+            match_spans: ptr::null(),
+            num_match_spans: 0,
+            column: -1,
+            byte_offset: byte_offset as i64,
+            edit_distance: -1,
+            scope_line_number: -1,
+            scope_bytes: ptr::null(),
+            scope_num_bytes: 0,
+            num_matching_lines: -1,
+            num_matches: -1,
+            truncated: 0,
+            line_offset: 0,
+            line_truncated: 0,
+        }
+    }
+
+    // Counts the matching lines, and the matches on them if the options ask for it
+    fn count(&mut self, searcher: &Searcher, lines: &Lines) -> Result<(), CallbackError> {
+        // in multi-line mode, one match can cover several lines
+        let num_lines = self.lines_before(lines.bytes, lines.bytes.len().saturating_sub(1)) + 1;
+        self.matching_lines += num_lines as u64;
+        if self.context.options.count != CountMode::Matches {
+            return Ok(());
+        }
+        if searcher.invert_match() {
+            self.matches += num_lines as u64;
+            return Ok(());
+        }
+        let mut matches = 0;
+        self.context
+            .matcher
+            .find_iter(self.searched(lines.bytes), |_| {
+                matches += 1;
+                true
+            })
+            .map_err(CallbackError::error_message)?;
+        self.matches += matches;
+        Ok(())
+    }
+
+    // A result with the counts for the whole file
+    fn file_result(&self, kind: SearchResultKind) -> SearchResult {
+        SearchResult {
+            byte_offset: -1,
+            num_matching_lines: self.matching_lines as i64,
+            num_matches: if self.context.options.count == CountMode::Matches {
+                self.matches as i64
+            } else {
+                -1
+            },
+            truncated: self.truncated as c_int,
+            line_offset: -1,
+            ..self.plain_result(&[], 0, kind)
+        }
+    }
+
+    // Calls back with the counts for the whole file, unless nothing in it matched
+    fn report_count(&self) -> Result<bool, CallbackError> {
+        if self.context.options.count == CountMode::None || self.matching_lines == 0 {
+            return Ok(true);
+        }
+        if !self.take_result() {
+            return Ok(false);
+        }
+        self.call_back(self.file_result(SearchResultKind::Count))
+    }
+
+    // Counts one more result towards `max_results`, unless it would go over, which truncates the search
+    fn take_result(&self) -> bool {
+        if self.reached_max_results() {
+            self.context.truncated.set(true);
+            return false;
+        }
+        let num_results = &self.context.num_results;
+        num_results.set(num_results.get() + 1);
+        true
+    }
+
+    fn reached_max_results(&self) -> bool {
+        let max_results = self.context.options.max_results;
+        max_results > 0 && self.context.num_results.get() >= max_results
+    }
+
+    fn reached_max_count(&self) -> bool {
+        let max_count = self.context.options.max_count;
+        max_count > 0 && self.num_matched >= max_count
+    }
+
+    // Reports lines past `max_count` as after-context of the last match counted, while there's room for them.
+    // Returns whether the search should carry on for more of it.
+    fn report_after_max_count(&mut self, lines: Lines) -> Result<bool, CallbackError> {
+        let terminator = self.context.options.line_terminator_byte();
+        let mut offset = 0;
+        // in multi-line mode, a match may take several lines, and only some may fit
+        for (i, line) in lines
+            .bytes
+            .split_inclusive(|&b| b == terminator)
+            .enumerate()
+        {
+            if self.after_context_left == 0 {
+                return Ok(false);
+            }
+            self.after_context_left -= 1;
+            let line = Lines {
+                bytes: line,
+                line_number: lines.line_number.map(|n| n + i as u64),
+                absolute_byte_offset: lines.absolute_byte_offset + offset as u64,
+            };
+            self.report_context_line(&line, SearchResultKind::AfterContext)?;
+            offset += line.bytes.len();
+        }
+        Ok(self.after_context_left > 0)
+    }
+
+    // Reports a line before or after the matches, which has nothing to highlight
+    fn report_context_line(
+        &self,
+        lines: &Lines,
+        kind: SearchResultKind,
+    ) -> Result<bool, CallbackError> {
+        let line_number = lines.line_number.map(|n| n as c_int).unwrap_or(-1);
+        let bytes = lines.bytes;
+        let trimmed = self.trimmed(bytes, 0..bytes.len());
+        // a context line has no match to center on, so its preview is the start of the line
+        let line_truncated = self.is_too_long(bytes, trimmed.clone());
+        let window = if line_truncated {
+            self.preview(bytes, trimmed, None)
+        } else {
+            trimmed
         };
+        let result = SearchResult {
+            line_number,
+            end_line_number: line_number,
+            line_offset: window.start as i64,
+            line_truncated: line_truncated as c_int,
+            ..self.plain_result(
+                &bytes[window.clone()],
+                lines.absolute_byte_offset + window.start as u64,
+                kind,
+            )
+        };
+        self.call_back(result)
+    }
+
+    fn scope_header_before(&mut self, offset: usize) -> Option<ScopeHeader<'a>> {
+        let headers = self.context.scope_headers.as_ref()?;
+        self.scope.as_mut()?.header_before(headers, offset)
+    }
 
-        let succeeded: bool = (self.0)(result);
+    fn call_back(&self, result: SearchResult) -> Result<bool, CallbackError> {
+        let succeeded: bool = (self.context.callback)(result);
         if succeeded {
             Ok(true) // callback done, keep searching
         } else {
-            Err(CallbackError::error_message(
-                "Callback completed but indicated an error",
-            ))
+            Err(CallbackError::from_callback())
+        }
+    }
+
+    // The 1-based column of the byte at `offset` within its line
+    fn column_at_offset(&self, bytes: &[u8], offset: usize) -> usize {
+        // in multi-line mode, the offset might not be on the first line of the result
+        let line_start = self.line_start(bytes, offset);
+        let options = self.context.options;
+        column_at(
+            &bytes[line_start..],
+            offset - line_start,
+            options.column_unit,
+            options.tab_width,
+        ) + 1
+    }
+
+    // Where the line holding the byte at `offset` starts, in the bytes of some whole lines
+    fn line_start(&self, bytes: &[u8], offset: usize) -> usize {
+        let terminator = self.context.options.line_terminator_byte();
+        bytes[..offset]
+            .iter()
+            .rposition(|&b| b == terminator)
+            .map(|i| i + 1)
+            .unwrap_or(0)
+    }
+
+    // The part of `range` to deliver, without its line terminator or indentation if the options say so
+    fn trimmed(&self, bytes: &[u8], range: Range<usize>) -> Range<usize> {
+        let options = self.context.options;
+        let (mut start, mut end) = (range.start, range.end);
+        if options.strip_line_terminator {
+            end = self.without_terminator(bytes, start..end).end;
+        }
+        if options.trim {
+            let terminator = options.line_terminator_byte();
+            let is_indentation =
+                |b: u8| b.is_ascii_whitespace() && b != b'\n' && b != b'\r' && b != terminator;
+            while start < end && is_indentation(bytes[start]) {
+                start += 1;
+            }
+        }
+        start..end
+    }
+
+    // The part of `range` before its line terminator, if it ends with one
+    fn without_terminator(&self, bytes: &[u8], range: Range<usize>) -> Range<usize> {
+        let terminator = self.context.options.line_terminator_byte();
+        let (start, mut end) = (range.start, range.end);
+        if end > start && bytes[end - 1] == terminator {
+            end -= 1;
+            // whether or not the search is in CRLF mode, a carriage return before the line feed goes too
+            if terminator == b'\n' && end > start && bytes[end - 1] == b'\r' {
+                end -= 1;
+            }
+        }
+        start..end
+    }
+
+    // Whether `range` is longer than `max_columns`, not counting its line terminator
+    fn is_too_long(&self, bytes: &[u8], range: Range<usize>) -> bool {
+        let max_columns = self.context.options.max_columns as usize;
+        max_columns > 0 && self.without_terminator(bytes, range).len() > max_columns
+    }
+
+    // The preview of an over-long `range`, which keeps the line terminator if it reaches the end of the line
+    fn preview(
+        &self,
+        bytes: &[u8],
+        range: Range<usize>,
+        focus: Option<Range<usize>>,
+    ) -> Range<usize> {
+        let width = self.context.options.max_columns as usize;
+        let content = self.without_terminator(bytes, range.clone());
+        let window = preview_window(bytes, content.clone(), focus, width);
+        if window.end == content.end {
+            window.start..range.end
+        } else {
+            window
+        }
+    }
+
+    // How many lines into the bytes of a matched line the byte at `offset` is
+    fn lines_before(&self, bytes: &[u8], offset: usize) -> usize {
+        let terminator = self.context.options.line_terminator_byte();
+        bytes[..offset].iter().filter(|&&b| b == terminator).count()
+    }
+}
+
+// Whole lines of a file, along with where they are in it
+struct Lines<'b> {
+    bytes: &'b [u8],
+    line_number: Option<u64>,
+    absolute_byte_offset: u64,
+}
+
+impl<'b> Lines<'b> {
+    fn matched(matched: &SinkMatch<'b>) -> Self {
+        Lines {
+            bytes: matched.bytes(),
+            line_number: matched.line_number(),
+            absolute_byte_offset: matched.absolute_byte_offset(),
+        }
+    }
+
+    fn context(context: &SinkContext<'b>) -> Self {
+        Lines {
+            bytes: context.bytes(),
+            line_number: context.line_number(),
+            absolute_byte_offset: context.absolute_byte_offset(),
+        }
+    }
+}
+
+// The lines read so far of the block being built up, when reporting whole blocks around matches
+#[derive(Default)]
+struct Block {
+    bytes: Vec<u8>,
+    line_number: Option<u64>,
+    absolute_byte_offset: u64,
+    // whether any of the lines matched the pattern, regardless of `invert_match`
+    has_match: bool,
+}
+
+// The parts of a result which depend on which bytes of the matched lines it reports
+struct ReportedSpan {
+    range: Range<usize>,
+    captures: Vec<CaptureGroup>,
+    match_spans: Vec<MatchSpan>,
+    column: Option<usize>,
+    // the match to center a preview on, if the range is too long; otherwise it's the first match in the range
+    focus: Option<Range<usize>>,
+}
+
+impl ReportedSpan {
+    // Cuts the range down to a preview window within it.
+    // The captures and match spans of matches entirely outside the window are dropped,
+    // and the rest are clamped to the window and made relative to it.
+    fn clip_to(&mut self, window: Range<usize>) {
+        let shift = (window.start - self.range.start) as c_int;
+        let len = window.len() as c_int;
+        let clip = |start: c_int, end: c_int| {
+            let (start, end) = (start - shift, end - shift);
+            let overlaps = (start < len && end > 0) || (start == end && (0..=len).contains(&start));
+            overlaps.then(|| (start.clamp(0, len), end.clamp(0, len)))
+        };
+
+        self.match_spans = self
+            .match_spans
+            .iter()
+            .filter_map(|span| clip(span.start, span.end))
+            .map(|(start, end)| MatchSpan { start, end })
+            .collect();
+        let kept_matches: Vec<c_int> = self
+            .captures
+            .iter()
+            .filter(|group| group.group_index == 0 && clip(group.start, group.end).is_some())
+            .map(|group| group.match_index)
+            .collect();
+        self.captures = self
+            .captures
+            .iter()
+            .filter(|group| kept_matches.contains(&group.match_index))
+            .map(|group| {
+                let clamp = |offset: c_int| {
+                    if offset < 0 {
+                        -1
+                    } else {
+                        (offset - shift).clamp(0, len)
+                    }
+                };
+                CaptureGroup {
+                    start: clamp(group.start),
+                    end: clamp(group.end),
+                    ..*group
+                }
+            })
+            .collect();
+        self.range = window;
+    }
+}
+
+pub struct CallbackError {
+    error_message: String,
+    // what the search returns because of the error
+    status_code: SearchStatusCode,
+}
+
+impl CallbackError {
+    // The callback itself asked to stop, rather than anything going wrong in ripgrep
+    fn from_callback() -> Self {
+        Self {
+            error_message: "Callback completed but indicated an error".to_string(),
+            status_code: SearchStatusCode::ErrorFromCallback,
+        }
+    }
+
+    pub fn status_code(&self) -> SearchStatusCode {
+        self.status_code
+    }
+}
+
+impl fmt::Display for CallbackError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.error_message)
+    }
+}
+
+// Errors from the searcher or the matcher, as opposed to the callback
+impl SinkError for CallbackError {
+    fn error_message<T: fmt::Display>(message: T) -> Self {
+        Self {
+            error_message: format!("{}", message),
+            status_code: SearchStatusCode::ErrorFromRipgrep,
+        }
+    }
+
+    fn error_io(error: io::Error) -> Self {
+        Self {
+            error_message: format!("{}", error),
+            status_code: SearchStatusCode::ErrorCouldNotOpenFile,
+        }
+    }
+}
+
+// Only finds out whether a file has a match, stopping at the first one
+#[derive(Default)]
+pub struct FirstMatchSink {
+    pub found: bool,
+}
+
+impl Sink for FirstMatchSink {
+    type Error = CallbackError;
+
+    fn matched(
+        &mut self,
+        _searcher: &Searcher,
+        _matched: &SinkMatch,
+    ) -> Result<bool, CallbackError> {
+        self.found = true;
+        Ok(false)
+    }
+}
+
+impl<M: ResultMatcher> Sink for SearchResultCallbackSink<'_, M> {
+    type Error = CallbackError;
+
+    fn matched(&mut self, searcher: &Searcher, matched: &SinkMatch) -> Result<bool, CallbackError> {
+        // The search only carries on past `max_count` to find out whether there's any more to the file,
+        // and to report the rest of the last match's after-context, which may match too
+        if self.reached_max_count() {
+            self.truncated = true;
+            return self.report_after_max_count(Lines::matched(matched));
+        }
+        self.num_matched += 1;
+        self.count(searcher, &Lines::matched(matched))?;
+        if self.context.options.count != CountMode::None {
+            return Ok(true);
+        }
+        if self.context.options.block_context != BlockContext::None {
+            let is_match = !searcher.invert_match();
+            return self.read_block_lines(searcher, Lines::matched(matched), is_match);
+        }
+        self.after_context_left = self.context.options.after_context as u64;
+        self.report_lines(searcher, &Lines::matched(matched))
+    }
+
+    fn context(
+        &mut self,
+        searcher: &Searcher,
+        context: &SinkContext,
+    ) -> Result<bool, CallbackError> {
+        // block context passes every line through, so it can tell where each block starts
+        if self.context.options.block_context != BlockContext::None {
+            let is_match = searcher.invert_match();
+            return self.read_block_lines(searcher, Lines::context(context), is_match);
+        }
+        // nothing after `max_count` or `max_results` is reported, except the after-context of the last match
+        let reached_max = self.reached_max_count() || self.reached_max_results();
+        if reached_max && matches!(context.kind(), SinkContextKind::Before) {
+            return Ok(true);
+        }
+        if self.truncated {
+            return self.report_after_max_count(Lines::context(context));
+        }
+        let kind = match context.kind() {
+            SinkContextKind::Before => SearchResultKind::BeforeContext,
+            // the only other kind is for `passthru`, which is only turned on for block context
+            _ => SearchResultKind::AfterContext,
+        };
+        if kind == SearchResultKind::AfterContext {
+            self.after_context_left = self.after_context_left.saturating_sub(1);
+        }
+        self.report_context_line(&Lines::context(context), kind)
+    }
+
+    fn context_break(&mut self, _searcher: &Searcher) -> Result<bool, CallbackError> {
+        if self.reached_max_count() || self.reached_max_results() {
+            return Ok(true);
+        }
+        let result = SearchResult {
+            byte_offset: -1,
+            line_offset: -1,
+            ..self.plain_result(&[], 0, SearchResultKind::ContextBreak)
+        };
+        self.call_back(result)
+    }
+
+    fn finish(&mut self, searcher: &Searcher, _: &SinkFinish) -> Result<(), CallbackError> {
+        // the last block ends with the file
+        self.flush_block(searcher)?;
+        self.report_count()?;
+        if self.context.options.report_file_end {
+            self.call_back(self.file_result(SearchResultKind::FileEnd))?;
         }
+        Ok(())
     }
 }